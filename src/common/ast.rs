@@ -1,4 +1,7 @@
-use super::token::Token;
+use super::{
+    position::Position,
+    token::{Token, TokenType},
+};
 
 pub type Program = Vec<Statement>;
 
@@ -7,7 +10,11 @@ pub enum Statement {
     Let(LetStatement),
     Assignment(AssignmentStatement),
     Function(FunctionStatement),
+    While(WhileStatement),
+    For(ForStatement),
     Return(Expression),
+    Break(Token),
+    Continue(Token),
     Expression(Expression),
 }
 
@@ -32,39 +39,77 @@ impl LetStatement {
     }
 }
 
+/// An assignment to a variable. The indices are empty unless an element is being assigned,
+/// e.g. `arr[0][1] = v`.
 #[derive(Debug, Clone)]
 pub struct AssignmentStatement {
     pub identifier: Token,
+    pub indices: Vec<Expression>,
     pub expression: Expression,
 }
 
 impl AssignmentStatement {
-    pub fn new(identifier: Token, expression: Expression) -> Self {
+    pub fn new(identifier: Token, indices: Vec<Expression>, expression: Expression) -> Self {
         Self {
             identifier,
+            indices,
             expression,
         }
     }
 }
 
+#[derive(Debug, Clone)]
+pub struct WhileStatement {
+    pub condition: Expression,
+    pub block: BlockExpression,
+}
+
+impl WhileStatement {
+    pub fn new(condition: Expression, block: BlockExpression) -> Self {
+        Self { condition, block }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ForStatement {
+    pub identifier: Token,
+    pub iterable: Expression,
+    pub block: BlockExpression,
+}
+
+impl ForStatement {
+    pub fn new(identifier: Token, iterable: Expression, block: BlockExpression) -> Self {
+        Self {
+            identifier,
+            iterable,
+            block,
+        }
+    }
+}
+
+/// A block, the position covers it's curly braces.
 #[derive(Debug, Clone)]
 pub struct BlockExpression {
     pub statements: Box<Vec<Statement>>,
+    pub position: Position,
 }
 
 impl BlockExpression {
-    pub fn new(statements: Vec<Statement>) -> Self {
+    pub fn new(statements: Vec<Statement>, position: Position) -> Self {
         Self {
             statements: Box::new(statements),
+            position,
         }
     }
 }
 
+/// An `if` expression, the position covers it from the `if` to the end of the last block.
 #[derive(Debug, Clone)]
 pub struct IfExpression {
     pub condition: Box<Expression>,
     pub if_block: BlockExpression,
     pub else_block: Box<Option<ElseBlock>>,
+    pub position: Position,
 }
 
 impl IfExpression {
@@ -72,11 +117,13 @@ impl IfExpression {
         condition: Expression,
         if_block: BlockExpression,
         else_block: Option<ElseBlock>,
+        position: Position,
     ) -> Self {
         Self {
             condition: Box::new(condition),
             if_block,
             else_block: Box::new(else_block),
+            position,
         }
     }
 }
@@ -85,6 +132,8 @@ impl IfExpression {
 pub struct Parameter {
     pub identifier: Token,
     pub is_pack: bool,
+    // The value the parameter takes when the call has no argument for it, e.g. `b = 10`.
+    pub default: Option<Expression>,
 }
 
 impl Parameter {
@@ -92,8 +141,14 @@ impl Parameter {
         Self {
             identifier,
             is_pack,
+            default: None,
         }
     }
+
+    pub fn with_default(mut self, default: Expression) -> Self {
+        self.default = Some(default);
+        self
+    }
 }
 
 /// A function statement. The block will be None if the function is a builtin function.
@@ -102,6 +157,8 @@ pub struct FunctionStatement {
     pub identifier: Token,
     pub paramiters: Vec<Parameter>,
     pub block: Option<BlockExpression>,
+    // The `///` comments right before the function, without the slashes.
+    pub doc: Option<String>,
 }
 
 impl FunctionStatement {
@@ -114,6 +171,7 @@ impl FunctionStatement {
             identifier,
             paramiters,
             block,
+            doc: None,
         }
     }
 }
@@ -121,6 +179,8 @@ impl FunctionStatement {
 #[derive(Debug, Clone)]
 pub enum Expression {
     Block(BlockExpression),
+    // An anonymous function, e.g. `func(x) { x * 2 }`. It's identifier is named `lambda`.
+    Function(FunctionStatement),
     If(IfExpression),
     Binary(BinaryExpression),
     Unary(UnaryExpression),
@@ -128,7 +188,52 @@ pub enum Expression {
     Call(CallExpression),
     Identifier(IdentifierExpression),
     Literal(LiteralExpression),
+    Interpolation(InterpolationExpression),
     Array(ArrayExpression),
+    Map(MapExpression),
+    Range(RangeExpression),
+    Index(IndexExpression),
+}
+
+impl Expression {
+    /// Returns the position that covers the whole expression.
+    pub fn position(&self) -> Position {
+        match self {
+            Self::Block(block_expression) => block_expression.position.clone(),
+            Self::Function(function_statement) => match &function_statement.block {
+                Some(block_expression) => function_statement
+                    .identifier
+                    .position
+                    .to(&block_expression.position),
+                None => function_statement.identifier.position.clone(),
+            },
+            Self::If(if_expression) => if_expression.position.clone(),
+            Self::Binary(binary_expression) => binary_expression
+                .left
+                .position()
+                .to(&binary_expression.right.position()),
+            Self::Unary(unary_expression) => unary_expression
+                .operator
+                .position
+                .to(&unary_expression.right.position()),
+            Self::Group(group_expression) => group_expression.position.clone(),
+            Self::Call(call_expression) => call_expression.position.clone(),
+            Self::Identifier(identifier_expression) => {
+                identifier_expression.identifier.position.clone()
+            }
+            Self::Literal(literal_expression) => literal_expression.object.position.clone(),
+            Self::Interpolation(interpolation_expression) => {
+                interpolation_expression.position.clone()
+            }
+            Self::Array(array_expression) => array_expression.position.clone(),
+            Self::Map(map_expression) => map_expression.position.clone(),
+            Self::Range(range_expression) => range_expression
+                .start
+                .position()
+                .to(&range_expression.end.position()),
+            Self::Index(index_expression) => index_expression.position.clone(),
+        }
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -163,30 +268,45 @@ impl UnaryExpression {
     }
 }
 
+/// An expression in parentheses, the position covers the parentheses.
 #[derive(Debug, Clone)]
 pub struct GroupExpression {
     pub child: Box<Expression>,
+    pub position: Position,
 }
 
 impl GroupExpression {
-    pub fn new(child: Expression) -> Self {
+    pub fn new(child: Expression, position: Position) -> Self {
         Self {
             child: Box::new(child),
+            position,
         }
     }
 }
 
+/// A call to anything that evaluates to a function. The position covers the callee and the arguments.
 #[derive(Debug, Clone)]
 pub struct CallExpression {
-    pub identifier: Token,
+    pub callee: Box<Expression>,
     pub arguments: Vec<Expression>,
+    // The arguments that are passed by the name of their parameter, e.g. `b: 3`. They come after
+    // the positional ones.
+    pub named: Vec<(Token, Expression)>,
+    pub position: Position,
 }
 
 impl CallExpression {
-    pub fn new(identifier: Token, arguments: Vec<Expression>) -> Self {
+    pub fn new(
+        callee: Expression,
+        arguments: Vec<Expression>,
+        named: Vec<(Token, Expression)>,
+        position: Position,
+    ) -> Self {
         Self {
-            identifier,
+            callee: Box::new(callee),
             arguments,
+            named,
+            position,
         }
     }
 }
@@ -213,13 +333,83 @@ impl LiteralExpression {
     }
 }
 
+/// A string with embedded expressions, e.g. `"hello ${name}"`. The parts are the pieces of the
+/// string and the embedded expressions in order, their values are joined into one string.
+#[derive(Debug, Clone)]
+pub struct InterpolationExpression {
+    pub parts: Vec<Expression>,
+    pub position: Position,
+}
+
+impl InterpolationExpression {
+    pub fn new(parts: Vec<Expression>, position: Position) -> Self {
+        Self { parts, position }
+    }
+}
+
+/// An array literal, the position covers it's brackets.
 #[derive(Debug, Clone)]
 pub struct ArrayExpression {
-    pub objects: Vec<Token>,
+    pub elements: Vec<Expression>,
+    pub position: Position,
 }
 
 impl ArrayExpression {
-    pub fn new(objects: Vec<Token>) -> Self {
-        Self { objects }
+    pub fn new(elements: Vec<Expression>, position: Position) -> Self {
+        Self { elements, position }
+    }
+}
+
+/// A map literal, e.g. `{"key": value}`. The position covers it's curly braces.
+#[derive(Debug, Clone)]
+pub struct MapExpression {
+    pub entries: Vec<(Expression, Expression)>,
+    pub position: Position,
+}
+
+impl MapExpression {
+    pub fn new(entries: Vec<(Expression, Expression)>, position: Position) -> Self {
+        Self { entries, position }
+    }
+}
+
+/// A range of numbers, `start..end` excludes the end and `start..=end` includes it.
+#[derive(Debug, Clone)]
+pub struct RangeExpression {
+    pub start: Box<Expression>,
+    pub operator: Token,
+    pub end: Box<Expression>,
+}
+
+impl RangeExpression {
+    pub fn new(start: Expression, operator: Token, end: Expression) -> Self {
+        Self {
+            start: Box::new(start),
+            operator,
+            end: Box::new(end),
+        }
+    }
+
+    pub fn is_inclusive(&self) -> bool {
+        self.operator.ttype == TokenType::DotDotEqual
+    }
+}
+
+/// An index into an array or a string, e.g. `arr[2]`. The index can also be a range, e.g. `s[1..3]`.
+/// The position covers the object and the brackets.
+#[derive(Debug, Clone)]
+pub struct IndexExpression {
+    pub object: Box<Expression>,
+    pub index: Box<Expression>,
+    pub position: Position,
+}
+
+impl IndexExpression {
+    pub fn new(object: Expression, index: Expression, position: Position) -> Self {
+        Self {
+            object: Box::new(object),
+            index: Box::new(index),
+            position,
+        }
     }
 }
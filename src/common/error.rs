@@ -6,7 +6,11 @@ use super::position::Position;
 pub enum ErrorType {
     LexingError,
     ParsingError,
+    ResolvingError,
     RuntimeError,
+    IoError,
+    // Not a failure, the program called `exit`. It's raised like an error so every running call stops.
+    Exit(i32),
 }
 
 impl Display for ErrorType {
@@ -14,16 +18,43 @@ impl Display for ErrorType {
         match self {
             Self::LexingError => write!(f, "LexingError"),
             Self::ParsingError => write!(f, "ParsingError"),
+            Self::ResolvingError => write!(f, "ResolvingError"),
             Self::RuntimeError => write!(f, "RuntimeError"),
+            Self::IoError => write!(f, "IoError"),
+            Self::Exit(code) => write!(f, "Exit({})", code),
         }
     }
 }
 
+/// A function call that was running when a runtime error happened.
+#[derive(Debug, Clone)]
+pub struct StackFrame {
+    pub function: String,
+    // Where the function was called from.
+    pub position: Position,
+}
+
+impl StackFrame {
+    pub fn new(function: String, position: Position) -> Self {
+        Self { function, position }
+    }
+}
+
+// Extra lines that are printed under the source snippet.
+#[derive(Debug, Clone)]
+enum Annotation {
+    Note(String),
+    Help(String),
+    // The calls from the innermost to the outermost one.
+    Traceback(Vec<StackFrame>),
+}
+
 #[derive(Debug, Clone)]
 pub struct Error {
     e_type: ErrorType,
     message: String,
     position: Position,
+    annotations: Vec<Annotation>,
 }
 
 impl Error {
@@ -32,13 +63,166 @@ impl Error {
             e_type,
             message,
             position,
+            annotations: Vec::new(),
         }
     }
 
+    pub fn with_note(mut self, note: String) -> Self {
+        self.annotations.push(Annotation::Note(note));
+        self
+    }
+
+    pub fn with_help(mut self, help: String) -> Self {
+        self.annotations.push(Annotation::Help(help));
+        self
+    }
+
+    /// Attaches the call stack to a runtime error, unless an inner call already did.
+    pub fn with_traceback(mut self, call_stack: &[StackFrame]) -> Self {
+        let has_traceback = self
+            .annotations
+            .iter()
+            .any(|annotation| matches!(annotation, Annotation::Traceback(_)));
+        if matches!(self.e_type, ErrorType::RuntimeError)
+            && !has_traceback
+            && !call_stack.is_empty()
+        {
+            let frames = call_stack.iter().rev().cloned().collect();
+            self.annotations.push(Annotation::Traceback(frames));
+        }
+        self
+    }
+
+    /// The status the process exits with because of this error.
+    pub fn exit_code(&self) -> i32 {
+        match self.e_type {
+            ErrorType::RuntimeError => 1,
+            ErrorType::LexingError => 2,
+            ErrorType::ParsingError => 3,
+            ErrorType::ResolvingError => 4,
+            ErrorType::IoError => 5,
+            ErrorType::Exit(code) => code,
+        }
+    }
+
+    /// Whether the program asked to stop, rather than failed.
+    pub fn is_exit(&self) -> bool {
+        matches!(self.e_type, ErrorType::Exit(_))
+    }
+
     pub fn report(&self) {
-        eprintln!(
-            "{}: {} in line {} (file: {}).",
-            self.e_type, self.message, self.position.row, self.position.source_path
-        );
+        if !self.is_exit() {
+            eprintln!("{}", self);
+        }
+    }
+}
+
+// Renders the error like rustc does, with the source line and a caret under the span.
+//
+// RuntimeError: The `f` expected 2 arguments but got 1. Missing arguments are `b`
+//  --> examples/example.func:3:1
+//   |
+// 3 | f(1)
+//   | ^^^^
+//   = note: `f` is declared as `f(a, b)`
+impl Display for Error {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        writeln!(f, "{}: {}", self.e_type, self.message)?;
+
+        let gutter = " ".repeat(self.position.row.to_string().len());
+        write!(f, "{}--> {}", gutter, self.position)?;
+        if let Some((line, span)) = self.position.line() {
+            // Tabs are kept, so the caret lines up with the source line.
+            let indent: String = line[..span.start]
+                .chars()
+                .map(|c| if c == '\t' { '\t' } else { ' ' })
+                .collect();
+            let width = line[span.start..span.end].chars().count().max(1);
+            write!(f, "\n{} |", gutter)?;
+            write!(f, "\n{} | {}", self.position.row, line)?;
+            write!(f, "\n{} | {}{}", gutter, indent, "^".repeat(width))?;
+        }
+        for annotation in &self.annotations {
+            match annotation {
+                Annotation::Note(note) => write!(f, "\n{} = note: {}", gutter, note)?,
+                Annotation::Help(help) => write!(f, "\n{} = help: {}", gutter, help)?,
+                Annotation::Traceback(frames) => write_traceback(f, frames)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+// Deep recursions repeat the same call over and over, so the repeated calls are folded into one line.
+fn write_traceback(f: &mut Formatter<'_>, frames: &[StackFrame]) -> Result {
+    const SHOWN_REPEATS: usize = 3;
+
+    write!(f, "\nTraceback (innermost call first):")?;
+    let mut index = 0;
+    while index < frames.len() {
+        let frame = &frames[index];
+        let repeats = frames[index..]
+            .iter()
+            .take_while(|other| {
+                other.function == frame.function
+                    && other.position.source_path == frame.position.source_path
+                    && other.position.span == frame.position.span
+            })
+            .count();
+        for _ in 0..repeats.min(SHOWN_REPEATS) {
+            write!(
+                f,
+                "\n  in `{}`, called from {}",
+                frame.function, frame.position
+            )?;
+        }
+        if repeats > SHOWN_REPEATS {
+            write!(
+                f,
+                "\n  [the call above is repeated {} more times]",
+                repeats - SHOWN_REPEATS
+            )?;
+        }
+        index += repeats;
+    }
+    Ok(())
+}
+
+/// Every error that was found in a single run, e.g. all of the syntax errors of a file.
+#[derive(Debug, Clone)]
+pub struct Diagnostics {
+    errors: Vec<Error>,
+}
+
+impl Diagnostics {
+    pub fn new(errors: Vec<Error>) -> Self {
+        Self { errors }
+    }
+
+    /// The status of the first error, the others are usually caused by it.
+    pub fn exit_code(&self) -> i32 {
+        self.errors.first().map_or(0, Error::exit_code)
+    }
+
+    pub fn is_exit(&self) -> bool {
+        self.errors.iter().any(Error::is_exit)
+    }
+
+    pub fn report(&self) {
+        for (index, error) in self.errors.iter().enumerate() {
+            if index > 0 {
+                eprintln!();
+            }
+            error.report();
+        }
+        if self.errors.len() > 1 {
+            eprintln!("\nFound {} errors.", self.errors.len());
+        }
+    }
+}
+
+impl From<Error> for Diagnostics {
+    fn from(error: Error) -> Self {
+        Self::new(vec![error])
     }
 }
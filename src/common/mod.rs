@@ -1,5 +1,6 @@
 pub mod ast;
 pub mod error;
+pub mod number;
 pub mod object;
 pub mod position;
 pub mod token;
@@ -0,0 +1,690 @@
+use std::{
+    cmp::Ordering,
+    fmt,
+    ops::{Add, Mul, Neg, Sub},
+};
+
+// The digits are kept in base 10^9, so every limb prints as nine decimal digits.
+const BASE: u64 = 1_000_000_000;
+const BASE_DIGITS: usize = 9;
+
+// The fractional digits a division adds on top of the scales of it's operands, e.g. `1d / 3d`.
+const DIVISION_DIGITS: u32 = 20;
+
+/// An integer without a size limit, written with the `n` suffix, e.g. `123n`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BigInt {
+    negative: bool,
+    // The least significant limb comes first and the last one is never zero, so zero has no limbs.
+    limbs: Vec<u32>,
+}
+
+impl BigInt {
+    pub fn zero() -> Self {
+        Self {
+            negative: false,
+            limbs: Vec::new(),
+        }
+    }
+
+    /// Parses decimal digits with an optional sign, e.g. `-123`.
+    pub fn parse(text: &str) -> Option<Self> {
+        let (negative, digits) = match text.strip_prefix('-') {
+            Some(digits) => (true, digits),
+            None => (false, text.strip_prefix('+').unwrap_or(text)),
+        };
+        if digits.is_empty() || !digits.bytes().all(|digit| digit.is_ascii_digit()) {
+            return None;
+        }
+
+        let mut limbs = Vec::with_capacity(digits.len() / BASE_DIGITS + 1);
+        let mut end = digits.len();
+        while end > 0 {
+            let start = end.saturating_sub(BASE_DIGITS);
+            limbs.push(digits[start..end].parse().ok()?);
+            end = start;
+        }
+        Some(Self::from_limbs(negative, limbs))
+    }
+
+    /// Parses digits in the given base without a sign, e.g. `ff` in base 16.
+    pub fn parse_radix(digits: &str, radix: u32) -> Option<Self> {
+        if digits.is_empty() {
+            return None;
+        }
+        let base = Self::from(radix as i64);
+        digits.chars().try_fold(Self::zero(), |value, digit| {
+            let digit = Self::from(digit.to_digit(radix)? as i64);
+            Some(&(&value * &base) + &digit)
+        })
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.limbs.is_empty()
+    }
+
+    pub fn is_negative(&self) -> bool {
+        self.negative
+    }
+
+    /// Returns `10^exponent`.
+    pub fn power_of_ten(exponent: u32) -> Self {
+        let exponent = exponent as usize;
+        let mut limbs = vec![0; exponent / BASE_DIGITS];
+        limbs.push(10u32.pow((exponent % BASE_DIGITS) as u32));
+        Self::from_limbs(false, limbs)
+    }
+
+    /// Divides toward zero and returns the quotient and the remainder, the remainder has the sign
+    /// of the dividend like `%` on integers. Returns None if the divisor is zero.
+    pub fn div_rem(&self, other: &Self) -> Option<(Self, Self)> {
+        if other.is_zero() {
+            return None;
+        }
+        let (quotient, remainder) = div_rem_limbs(&self.limbs, &other.limbs);
+        Some((
+            Self::from_limbs(self.negative != other.negative, quotient),
+            Self::from_limbs(self.negative, remainder),
+        ))
+    }
+
+    pub fn to_i64(&self) -> Option<i64> {
+        self.to_string().parse().ok()
+    }
+
+    pub fn to_f64(&self) -> f64 {
+        self.to_string().parse().unwrap_or(f64::NAN)
+    }
+
+    fn from_limbs(negative: bool, mut limbs: Vec<u32>) -> Self {
+        while limbs.last() == Some(&0) {
+            limbs.pop();
+        }
+        Self {
+            negative: negative && !limbs.is_empty(),
+            limbs,
+        }
+    }
+
+    // Adds the magnitudes if the signs are the same, and subtracts the smaller one otherwise.
+    fn add_signed(&self, other: &Self, other_negative: bool) -> Self {
+        if self.negative == other_negative {
+            return Self::from_limbs(self.negative, add_limbs(&self.limbs, &other.limbs));
+        }
+        match compare_limbs(&self.limbs, &other.limbs) {
+            Ordering::Less => {
+                Self::from_limbs(other_negative, sub_limbs(&other.limbs, &self.limbs))
+            }
+            _ => Self::from_limbs(self.negative, sub_limbs(&self.limbs, &other.limbs)),
+        }
+    }
+}
+
+impl From<i64> for BigInt {
+    fn from(integer: i64) -> Self {
+        let mut magnitude = integer.unsigned_abs();
+        let mut limbs = Vec::new();
+        while magnitude > 0 {
+            limbs.push((magnitude % BASE) as u32);
+            magnitude /= BASE;
+        }
+        Self::from_limbs(integer < 0, limbs)
+    }
+}
+
+impl Add for &BigInt {
+    type Output = BigInt;
+
+    fn add(self, other: Self) -> BigInt {
+        self.add_signed(other, other.negative)
+    }
+}
+
+impl Sub for &BigInt {
+    type Output = BigInt;
+
+    fn sub(self, other: Self) -> BigInt {
+        self.add_signed(other, !other.negative)
+    }
+}
+
+impl Mul for &BigInt {
+    type Output = BigInt;
+
+    fn mul(self, other: Self) -> BigInt {
+        BigInt::from_limbs(
+            self.negative != other.negative,
+            mul_limbs(&self.limbs, &other.limbs),
+        )
+    }
+}
+
+impl Neg for &BigInt {
+    type Output = BigInt;
+
+    fn neg(self) -> BigInt {
+        BigInt::from_limbs(!self.negative, self.limbs.clone())
+    }
+}
+
+impl Ord for BigInt {
+    fn cmp(&self, other: &Self) -> Ordering {
+        match (self.negative, other.negative) {
+            (false, true) => Ordering::Greater,
+            (true, false) => Ordering::Less,
+            (false, false) => compare_limbs(&self.limbs, &other.limbs),
+            (true, true) => compare_limbs(&other.limbs, &self.limbs),
+        }
+    }
+}
+
+impl PartialOrd for BigInt {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl fmt::Display for BigInt {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.negative {
+            write!(f, "-")?;
+        }
+        match self.limbs.split_last() {
+            Some((last, rest)) => {
+                write!(f, "{}", last)?;
+                for limb in rest.iter().rev() {
+                    write!(f, "{:09}", limb)?;
+                }
+                Ok(())
+            }
+            None => write!(f, "0"),
+        }
+    }
+}
+
+fn compare_limbs(a: &[u32], b: &[u32]) -> Ordering {
+    a.len()
+        .cmp(&b.len())
+        .then_with(|| a.iter().rev().cmp(b.iter().rev()))
+}
+
+fn add_limbs(a: &[u32], b: &[u32]) -> Vec<u32> {
+    let mut limbs = Vec::with_capacity(a.len().max(b.len()) + 1);
+    let mut carry = 0;
+    for index in 0..a.len().max(b.len()) {
+        let sum = *a.get(index).unwrap_or(&0) as u64 + *b.get(index).unwrap_or(&0) as u64 + carry;
+        limbs.push((sum % BASE) as u32);
+        carry = sum / BASE;
+    }
+    if carry > 0 {
+        limbs.push(carry as u32);
+    }
+    limbs
+}
+
+// Expects `a` to be at least `b`.
+fn sub_limbs(a: &[u32], b: &[u32]) -> Vec<u32> {
+    let mut limbs = Vec::with_capacity(a.len());
+    let mut borrow = 0;
+    for (index, limb) in a.iter().enumerate() {
+        let subtrahend = *b.get(index).unwrap_or(&0) as i64 + borrow;
+        let mut difference = *limb as i64 - subtrahend;
+        borrow = 0;
+        if difference < 0 {
+            difference += BASE as i64;
+            borrow = 1;
+        }
+        limbs.push(difference as u32);
+    }
+    limbs
+}
+
+fn mul_limbs(a: &[u32], b: &[u32]) -> Vec<u32> {
+    let mut limbs = vec![0u64; a.len() + b.len()];
+    for (i, x) in a.iter().enumerate() {
+        let mut carry = 0;
+        for (j, y) in b.iter().enumerate() {
+            let product = limbs[i + j] + *x as u64 * *y as u64 + carry;
+            limbs[i + j] = product % BASE;
+            carry = product / BASE;
+        }
+        limbs[i + b.len()] += carry;
+    }
+    limbs.into_iter().map(|limb| limb as u32).collect()
+}
+
+// Long division, one limb of the quotient at a time. Each limb is found with a binary search for
+// the largest multiple of the divisor that fits in the remainder.
+fn div_rem_limbs(a: &[u32], b: &[u32]) -> (Vec<u32>, Vec<u32>) {
+    let mut quotient = vec![0; a.len()];
+    let mut remainder: Vec<u32> = Vec::new();
+    for index in (0..a.len()).rev() {
+        remainder.insert(0, a[index]);
+        while remainder.last() == Some(&0) {
+            remainder.pop();
+        }
+
+        let (mut low, mut high) = (0, BASE as u32 - 1);
+        while low < high {
+            let middle = low + (high - low + 1) / 2;
+            if compare_limbs(&trimmed(mul_limbs(b, &[middle])), &remainder) == Ordering::Greater {
+                high = middle - 1;
+            } else {
+                low = middle;
+            }
+        }
+        if low > 0 {
+            remainder = trimmed(sub_limbs(&remainder, &trimmed(mul_limbs(b, &[low]))));
+        }
+        quotient[index] = low;
+    }
+    (quotient, remainder)
+}
+
+fn trimmed(mut limbs: Vec<u32>) -> Vec<u32> {
+    while limbs.last() == Some(&0) {
+        limbs.pop();
+    }
+    limbs
+}
+
+/// An exact decimal number, written with the `d` suffix, e.g. `1.10d`. It keeps the digits it was
+/// written with, so `1.10d` prints as `1.10`.
+#[derive(Debug, Clone)]
+pub struct Decimal {
+    // The value is `unscaled / 10^scale`.
+    unscaled: BigInt,
+    scale: u32,
+}
+
+impl Decimal {
+    /// Parses a decimal with an optional sign and fraction, e.g. `-12.50`.
+    pub fn parse(text: &str) -> Option<Self> {
+        let (whole, fraction) = text.split_once('.').unwrap_or((text, ""));
+        if whole.trim_start_matches(['-', '+']).is_empty()
+            || (text.contains('.') && fraction.is_empty())
+            || !fraction.bytes().all(|digit| digit.is_ascii_digit())
+        {
+            return None;
+        }
+        Some(Self {
+            unscaled: BigInt::parse(&format!("{}{}", whole, fraction))?,
+            scale: fraction.len() as u32,
+        })
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.unscaled.is_zero()
+    }
+
+    /// Rounds half away from zero to the given number of fractional digits. A decimal with fewer
+    /// digits is padded with zeros, so `round(1.5d, 2)` is `1.50`.
+    pub fn round(&self, places: u32) -> Self {
+        if places >= self.scale {
+            return Self {
+                unscaled: self.rescaled(places),
+                scale: places,
+            };
+        }
+        let factor = BigInt::power_of_ten(self.scale - places);
+        let (mut quotient, remainder) = self.unscaled.div_rem(&factor).unwrap();
+        let twice = &remainder + &remainder;
+        if compare_limbs(&twice.limbs, &factor.limbs) != Ordering::Less {
+            let one = BigInt::from(if self.unscaled.is_negative() { -1 } else { 1 });
+            quotient = &quotient + &one;
+        }
+        Self {
+            unscaled: quotient,
+            scale: places,
+        }
+    }
+
+    /// Divides with `20` more fractional digits than the operands have, the trailing zeros of the
+    /// extra digits are dropped. Returns None if the divisor is zero.
+    pub fn checked_div(&self, other: &Self) -> Option<Self> {
+        let scale = self.scale.max(other.scale);
+        let target = scale + DIVISION_DIGITS;
+        // One more digit than needed, so the quotient can be rounded.
+        let numerator =
+            &self.unscaled * &BigInt::power_of_ten(target + 1 + other.scale - self.scale);
+        let (quotient, _) = numerator.div_rem(&other.unscaled)?;
+        let mut result = Self {
+            unscaled: quotient,
+            scale: target + 1,
+        }
+        .round(target);
+
+        let ten = BigInt::from(10);
+        while result.scale > scale {
+            match result.unscaled.div_rem(&ten) {
+                Some((quotient, remainder)) if remainder.is_zero() => {
+                    result.unscaled = quotient;
+                    result.scale -= 1;
+                }
+                _ => break,
+            }
+        }
+        Some(result)
+    }
+
+    /// The remainder of dividing toward zero, it has the sign of the dividend. Returns None if the
+    /// divisor is zero.
+    pub fn checked_rem(&self, other: &Self) -> Option<Self> {
+        let scale = self.scale.max(other.scale);
+        let (_, remainder) = self.rescaled(scale).div_rem(&other.rescaled(scale))?;
+        Some(Self {
+            unscaled: remainder,
+            scale,
+        })
+    }
+
+    /// Drops the fraction, rounding toward zero.
+    pub fn trunc(&self) -> BigInt {
+        let (quotient, _) = self
+            .unscaled
+            .div_rem(&BigInt::power_of_ten(self.scale))
+            .unwrap();
+        quotient
+    }
+
+    pub fn to_f64(&self) -> f64 {
+        self.to_string().parse().unwrap_or(f64::NAN)
+    }
+
+    // The unscaled value with `scale` fractional digits, the scale can't be less than the current one.
+    fn rescaled(&self, scale: u32) -> BigInt {
+        &self.unscaled * &BigInt::power_of_ten(scale - self.scale)
+    }
+}
+
+impl From<BigInt> for Decimal {
+    fn from(unscaled: BigInt) -> Self {
+        Self { unscaled, scale: 0 }
+    }
+}
+
+impl Add for &Decimal {
+    type Output = Decimal;
+
+    fn add(self, other: Self) -> Decimal {
+        let scale = self.scale.max(other.scale);
+        Decimal {
+            unscaled: &self.rescaled(scale) + &other.rescaled(scale),
+            scale,
+        }
+    }
+}
+
+impl Sub for &Decimal {
+    type Output = Decimal;
+
+    fn sub(self, other: Self) -> Decimal {
+        let scale = self.scale.max(other.scale);
+        Decimal {
+            unscaled: &self.rescaled(scale) - &other.rescaled(scale),
+            scale,
+        }
+    }
+}
+
+impl Mul for &Decimal {
+    type Output = Decimal;
+
+    fn mul(self, other: Self) -> Decimal {
+        Decimal {
+            unscaled: &self.unscaled * &other.unscaled,
+            scale: self.scale + other.scale,
+        }
+    }
+}
+
+impl Neg for &Decimal {
+    type Output = Decimal;
+
+    fn neg(self) -> Decimal {
+        Decimal {
+            unscaled: -&self.unscaled,
+            scale: self.scale,
+        }
+    }
+}
+
+// Decimals are compared by their value, `1.10d` and `1.1d` are equal.
+impl PartialEq for Decimal {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Decimal {}
+
+impl Ord for Decimal {
+    fn cmp(&self, other: &Self) -> Ordering {
+        let scale = self.scale.max(other.scale);
+        self.rescaled(scale).cmp(&other.rescaled(scale))
+    }
+}
+
+impl PartialOrd for Decimal {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl fmt::Display for Decimal {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let digits = self.unscaled.to_string();
+        let (sign, digits) = match digits.strip_prefix('-') {
+            Some(digits) => ("-", digits),
+            None => ("", digits.as_str()),
+        };
+        let scale = self.scale as usize;
+        if scale == 0 {
+            return write!(f, "{}{}", sign, digits);
+        }
+        let digits = format!("{:0>width$}", digits, width = scale + 1);
+        let (whole, fraction) = digits.split_at(digits.len() - scale);
+        write!(f, "{}{}.{}", sign, whole, fraction)
+    }
+}
+
+/// Compares an integer with a float exactly, `integer as f64` would round the integers beyond
+/// 2^53. Returns None if the float is NaN.
+pub fn compare_integer_float(integer: i64, float: f64) -> Option<Ordering> {
+    // 2^63, every float below it in magnitude has a whole part that fits in an `i64`.
+    const LIMIT: f64 = 9_223_372_036_854_775_808.;
+    if float.is_nan() {
+        None
+    } else if float >= LIMIT {
+        Some(Ordering::Less)
+    } else if float < -LIMIT {
+        Some(Ordering::Greater)
+    } else {
+        let whole = float.trunc();
+        let fraction = float - whole;
+        Some(
+            integer
+                .cmp(&(whole as i64))
+                .then_with(|| 0f64.partial_cmp(&fraction).unwrap_or(Ordering::Equal)),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn big(text: &str) -> BigInt {
+        BigInt::parse(text).unwrap()
+    }
+
+    fn decimal(text: &str) -> Decimal {
+        Decimal::parse(text).unwrap()
+    }
+
+    #[test]
+    fn parse_and_display() {
+        assert_eq!(big("000123").to_string(), "123");
+        assert_eq!(big("-0").to_string(), "0");
+        assert_eq!(big("-1000000000000").to_string(), "-1000000000000");
+        assert_eq!(BigInt::parse(""), None);
+        assert_eq!(BigInt::parse("1_000"), None);
+        assert_eq!(
+            BigInt::parse_radix("ffffffffffffffff", 16).unwrap(),
+            big("18446744073709551615")
+        );
+        assert_eq!(BigInt::power_of_ten(18), big("1000000000000000000"));
+
+        assert_eq!(decimal("-0.05").to_string(), "-0.05");
+        assert_eq!(decimal("1.10").to_string(), "1.10");
+        assert_eq!(Decimal::parse("5."), None);
+        assert_eq!(Decimal::parse(".5"), None);
+        assert_eq!(Decimal::parse("-"), None);
+    }
+
+    #[test]
+    fn carry_across_limbs() {
+        assert_eq!(&big("999999999") + &big("1"), big("1000000000"));
+        assert_eq!(
+            &big("999999999999999999") + &big("1"),
+            big("1000000000000000000")
+        );
+        assert_eq!(
+            &big("-999999999999999999") + &big("-1"),
+            big("-1000000000000000000")
+        );
+        assert_eq!(
+            &big("999999999") * &big("999999999"),
+            big("999999998000000001")
+        );
+    }
+
+    #[test]
+    fn borrow_across_limbs() {
+        assert_eq!(&big("1000000000") - &big("1"), big("999999999"));
+        assert_eq!(
+            &big("1000000000000000000") - &big("1"),
+            big("999999999999999999")
+        );
+        assert_eq!(&big("5") - &big("1000000000"), big("-999999995"));
+        assert_eq!(&big("-1000000000") + &big("1000000000"), BigInt::zero());
+    }
+
+    #[test]
+    fn division_by_multi_limb_divisor() {
+        let dividend =
+            big("1881676377434183987554591826597870779196556262659441282631554954480361860897069");
+        let divisor = big("987654321987654321");
+        let (quotient, remainder) = dividend.div_rem(&divisor).unwrap();
+        assert_eq!(
+            quotient,
+            big("1905197330223098990524321529896679877534570820815222844119854")
+        );
+        assert_eq!(remainder, big("124918344615907935"));
+        assert_eq!(&(&quotient * &divisor) + &remainder, dividend);
+
+        let (quotient, remainder) = big("1000000000000000000000000005")
+            .div_rem(&big("1000000000000000007"))
+            .unwrap();
+        assert_eq!(quotient, big("999999999"));
+        assert_eq!(remainder, big("999999993000000012"));
+    }
+
+    #[test]
+    fn remainder_has_the_sign_of_the_dividend() {
+        let pairs = [
+            ("-7", "2", "-3", "-1"),
+            ("7", "-2", "-3", "1"),
+            ("-7", "-2", "3", "-1"),
+            ("-1000000000000000000", "3", "-333333333333333333", "-1"),
+        ];
+        for (dividend, divisor, quotient, remainder) in pairs {
+            assert_eq!(
+                big(dividend).div_rem(&big(divisor)),
+                Some((big(quotient), big(remainder)))
+            );
+        }
+        assert_eq!(big("1").div_rem(&BigInt::zero()), None);
+
+        assert_eq!(
+            decimal("-7.5").checked_rem(&decimal("2")).unwrap(),
+            decimal("-1.5")
+        );
+        assert_eq!(
+            decimal("7.5").checked_rem(&decimal("-2")).unwrap(),
+            decimal("1.5")
+        );
+        assert_eq!(decimal("1").checked_rem(&decimal("0.0")), None);
+    }
+
+    #[test]
+    fn round_half_away_from_zero() {
+        let cases = [
+            ("2.345", 2, "2.35"),
+            ("-2.345", 2, "-2.35"),
+            ("2.344", 2, "2.34"),
+            ("0.5", 0, "1"),
+            ("-0.5", 0, "-1"),
+            ("-0.4", 0, "0"),
+            ("9.995", 2, "10.00"),
+            ("1.5", 2, "1.50"),
+        ];
+        for (value, places, rounded) in cases {
+            assert_eq!(decimal(value).round(places).to_string(), rounded);
+        }
+    }
+
+    #[test]
+    fn decimal_division() {
+        let cases = [
+            ("1", "3", "0.33333333333333333333"),
+            ("2", "3", "0.66666666666666666667"),
+            ("-2", "3", "-0.66666666666666666667"),
+            ("10.00", "4", "2.50"),
+            ("1", "8", "0.125"),
+        ];
+        for (dividend, divisor, quotient) in cases {
+            assert_eq!(
+                decimal(dividend)
+                    .checked_div(&decimal(divisor))
+                    .unwrap()
+                    .to_string(),
+                quotient
+            );
+        }
+        assert_eq!(decimal("1").checked_div(&decimal("0")), None);
+    }
+
+    #[test]
+    fn integers_compare_with_floats_exactly() {
+        let cases = [
+            (
+                9_007_199_254_740_993,
+                9_007_199_254_740_992.,
+                Ordering::Greater,
+            ),
+            (
+                9_007_199_254_740_992,
+                9_007_199_254_740_992.,
+                Ordering::Equal,
+            ),
+            (i64::MAX, 9_223_372_036_854_775_808., Ordering::Less),
+            (i64::MIN, -9_223_372_036_854_775_808., Ordering::Equal),
+            (i64::MIN, f64::NEG_INFINITY, Ordering::Greater),
+            (2, 2.5, Ordering::Less),
+            (-2, -2.5, Ordering::Greater),
+            (0, -0., Ordering::Equal),
+        ];
+        for (integer, float, ordering) in cases {
+            assert_eq!(compare_integer_float(integer, float), Some(ordering));
+        }
+        assert_eq!(compare_integer_float(1, f64::NAN), None);
+    }
+
+    #[test]
+    fn decimals_compare_by_value() {
+        assert_eq!(decimal("1.10"), decimal("1.1"));
+        assert!(decimal("-1.5") < decimal("-1.25"));
+        assert_eq!(decimal("-2.9").trunc(), big("-2"));
+    }
+}
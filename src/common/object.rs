@@ -1,29 +1,287 @@
-use std::fmt;
+use std::{any::Any, cmp::Ordering, collections::BTreeMap, fmt, rc::Rc};
 
 use super::{
+    ast::{BlockExpression, FunctionStatement, Parameter},
     error::{Error, ErrorType},
+    number::{compare_integer_float, BigInt, Decimal},
     position::Position,
+    token::Token,
 };
 
 #[derive(Default, Debug, Clone, PartialEq)]
 pub struct Meta {
     pub is_return: bool,
+    pub is_break: bool,
+    pub is_continue: bool,
 }
 
-#[derive(Debug, Clone, PartialEq)]
+/// A function value. The block will be None if the function is a builtin function, or if it's
+/// created by the virtual machine. The runtime that creates a function captures whatever it needs
+/// to call it, e.g. the scope the function was defined in, builtins don't capture anything.
+#[derive(Clone)]
+pub struct Function {
+    pub identifier: Token,
+    pub paramiters: Vec<Parameter>,
+    pub block: Option<BlockExpression>,
+    pub captured: Option<Rc<dyn Any>>,
+    pub doc: Option<String>,
+}
+
+impl Function {
+    pub fn new(function_statement: FunctionStatement, captured: Option<Rc<dyn Any>>) -> Self {
+        Self {
+            identifier: function_statement.identifier,
+            paramiters: function_statement.paramiters,
+            block: function_statement.block,
+            captured,
+            doc: function_statement.doc,
+        }
+    }
+
+    pub fn is_builtin(&self) -> bool {
+        self.captured.is_none()
+    }
+
+    /// Returns how the function is declared, e.g. `add(a, b = 1)`.
+    pub fn signature(&self) -> String {
+        let paramiters = self
+            .paramiters
+            .iter()
+            .map(|p| match &p.default {
+                _ if p.is_pack => format!("...{}", p.identifier.lexeme),
+                Some(default) => format!(
+                    "{} = {}",
+                    p.identifier.lexeme,
+                    default.position().text().unwrap_or("...")
+                ),
+                None => p.identifier.lexeme.clone(),
+            })
+            .collect::<Vec<_>>()
+            .join(", ");
+        format!("{}({})", self.identifier.lexeme, paramiters)
+    }
+
+    /// Checks the arguments of a call before they are evaluated: every parameter without a default
+    /// needs an argument, and the named arguments must match a parameter that has no other argument.
+    pub fn check_arity(
+        &self,
+        arguments_length: usize,
+        names: &[Token],
+        position: Position,
+    ) -> Result<(), Error> {
+        let paramiters = &self.paramiters;
+        let signature = format!(
+            "`{}` is declared as `{}`",
+            self.identifier.lexeme,
+            self.signature()
+        );
+        // A pack takes any number of arguments, even none.
+        let positional = paramiters.iter().filter(|p| !p.is_pack).count();
+        let required = paramiters
+            .iter()
+            .filter(|p| !p.is_pack && p.default.is_none())
+            .count();
+        let has_pack = positional < paramiters.len();
+
+        if arguments_length > positional && !has_pack {
+            return Err(Error::new(
+                ErrorType::RuntimeError,
+                format!(
+                    "too many arguments passed to `{}`. Expected {}{} but got {}",
+                    self.identifier.lexeme,
+                    if required < positional {
+                        "at most "
+                    } else {
+                        ""
+                    },
+                    positional,
+                    arguments_length
+                ),
+                position,
+            )
+            .with_note(signature));
+        }
+
+        for (index, name) in names.iter().enumerate() {
+            let paramiter = paramiters
+                .iter()
+                .position(|p| !p.is_pack && p.identifier.lexeme == name.lexeme);
+            match paramiter {
+                None => {
+                    return Err(Error::new(
+                        ErrorType::RuntimeError,
+                        format!(
+                            "`{}` has no parameter named `{}`",
+                            self.identifier.lexeme, name.lexeme
+                        ),
+                        name.position.clone(),
+                    )
+                    .with_note(signature))
+                }
+                Some(paramiter)
+                    if paramiter < arguments_length
+                        || names[..index].iter().any(|n| n.lexeme == name.lexeme) =>
+                {
+                    return Err(Error::new(
+                        ErrorType::RuntimeError,
+                        format!(
+                            "The argument `{}` of `{}` is given more than once",
+                            name.lexeme, self.identifier.lexeme
+                        ),
+                        name.position.clone(),
+                    )
+                    .with_note(signature))
+                }
+                Some(_) => {}
+            }
+        }
+
+        let missing: Vec<String> = paramiters
+            .iter()
+            .enumerate()
+            .filter(|(index, p)| {
+                !p.is_pack
+                    && p.default.is_none()
+                    && *index >= arguments_length
+                    && !names.iter().any(|n| n.lexeme == p.identifier.lexeme)
+            })
+            .map(|(_, p)| format!("`{}`", p.identifier.lexeme))
+            .collect();
+        if !missing.is_empty() {
+            return Err(Error::new(
+                ErrorType::RuntimeError,
+                format!(
+                    "The `{}` expected {}{} arguments but got {}. Missing arguments are {}",
+                    self.identifier.lexeme,
+                    if required < paramiters.len() {
+                        "at least "
+                    } else {
+                        ""
+                    },
+                    required,
+                    arguments_length + names.len(),
+                    missing.join(", ")
+                ),
+                position,
+            )
+            .with_note(signature));
+        }
+        Ok(())
+    }
+
+    /// Returns the argument of every parameter in order, or None when it takes it's default value.
+    /// The positional arguments come first and the named ones after them, in the order of `names`.
+    /// The positional arguments that are left over are collected in an array for the pack. The
+    /// arity should be checked first.
+    pub fn bind_arguments(
+        &self,
+        mut arguments: Vec<Object>,
+        names: &[Token],
+    ) -> Vec<Option<Object>> {
+        let named = arguments.split_off(arguments.len().saturating_sub(names.len()));
+        let mut positional = arguments.into_iter();
+        let mut bound: Vec<Option<Object>> = self
+            .paramiters
+            .iter()
+            .map(|p| {
+                if p.is_pack {
+                    Some(Object::Array(
+                        positional.by_ref().collect(),
+                        Meta::default(),
+                    ))
+                } else {
+                    positional.next()
+                }
+            })
+            .collect();
+        for (name, value) in names.iter().zip(named) {
+            let paramiter = self
+                .paramiters
+                .iter()
+                .position(|p| p.identifier.lexeme == name.lexeme);
+            if let Some(paramiter) = paramiter {
+                bound[paramiter] = Some(value);
+            }
+        }
+        bound
+    }
+}
+
+impl fmt::Debug for Function {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Function")
+            .field("identifier", &self.identifier)
+            .field("paramiters", &self.paramiters)
+            .field("block", &self.block)
+            .field("doc", &self.doc)
+            .finish_non_exhaustive()
+    }
+}
+
+// Two functions are only equal if they are the very same function.
+impl PartialEq for Function {
+    fn eq(&self, other: &Self) -> bool {
+        std::ptr::eq(self, other)
+    }
+}
+
+#[derive(Debug, Clone)]
 pub enum Object {
+    Integer(i64, Meta),
     Number(f64, Meta),
+    BigInt(BigInt, Meta),
+    Decimal(Decimal, Meta),
     String(String, Meta),
     Boolean(bool, Meta),
     Array(Vec<Object>, Meta),
+    Map(BTreeMap<String, Object>, Meta),
+    Function(Rc<Function>, Meta),
     Nil(Meta),
 }
 
+// Numbers are equal if their values are, whatever their types, e.g. `[1] == [1.0]` like `1 == 1.0`.
+// A float is never equal to a big integer or a decimal, it's not exact. The control flow flags
+// aren't part of the value.
+impl PartialEq for Object {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Self::Integer(x, ..), Self::Integer(y, ..)) => x == y,
+            (Self::Number(x, ..), Self::Number(y, ..)) => x == y,
+            (Self::BigInt(x, ..), Self::BigInt(y, ..)) => x == y,
+            (Self::Decimal(x, ..), Self::Decimal(y, ..)) => x == y,
+            (Self::Integer(x, ..), Self::Number(y, ..))
+            | (Self::Number(y, ..), Self::Integer(x, ..)) => {
+                compare_integer_float(*x, *y) == Some(Ordering::Equal)
+            }
+            (Self::Integer(x, ..), Self::BigInt(y, ..))
+            | (Self::BigInt(y, ..), Self::Integer(x, ..)) => &BigInt::from(*x) == y,
+            (Self::Integer(x, ..), Self::Decimal(y, ..))
+            | (Self::Decimal(y, ..), Self::Integer(x, ..)) => &Decimal::from(BigInt::from(*x)) == y,
+            (Self::BigInt(x, ..), Self::Decimal(y, ..))
+            | (Self::Decimal(y, ..), Self::BigInt(x, ..)) => &Decimal::from(x.clone()) == y,
+            (Self::String(x, ..), Self::String(y, ..)) => x == y,
+            (Self::Boolean(x, ..), Self::Boolean(y, ..)) => x == y,
+            (Self::Array(x, ..), Self::Array(y, ..)) => x == y,
+            (Self::Map(x, ..), Self::Map(y, ..)) => x == y,
+            (Self::Function(x, ..), Self::Function(y, ..)) => x == y,
+            (Self::Nil(..), Self::Nil(..)) => true,
+            _ => false,
+        }
+    }
+}
+
 impl fmt::Display for Object {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
+            Self::Integer(integer, ..) => write!(f, "{}", integer),
+            // A whole float keeps it's point, so it can be told apart from an integer.
+            Self::Number(number, ..) if number.is_finite() && number.fract() == 0. => {
+                write!(f, "{:.1}", number)
+            }
             Self::Number(number, ..) => write!(f, "{}", number),
-            Self::String(string, ..) => write!(f, "{}", string.replace("\\n", "\n")),
+            Self::BigInt(integer, ..) => write!(f, "{}", integer),
+            Self::Decimal(decimal, ..) => write!(f, "{}", decimal),
+            Self::String(string, ..) => write!(f, "{}", string),
             Self::Boolean(boolean, ..) => write!(f, "{}", boolean),
             Self::Array(array, ..) => {
                 write!(f, "[")?;
@@ -33,40 +291,115 @@ impl fmt::Display for Object {
                 write!(f, "]")?;
                 Ok(())
             }
+            Self::Map(map, ..) => {
+                write!(f, "{{")?;
+                for (key, value) in map {
+                    write!(f, "{}: {},", key, value)?;
+                }
+                write!(f, "}}")?;
+                Ok(())
+            }
+            Self::Function(function, ..) => {
+                if function.is_builtin() {
+                    write!(f, "<builtin {}>", function.identifier.lexeme)
+                } else {
+                    write!(f, "<func {}>", function.identifier.lexeme)
+                }
+            }
             Self::Nil(..) => write!(f, "nil"),
         }
     }
 }
 
 impl Object {
+    /// Returns the object the way it would be written in the source, e.g. strings are quoted.
+    /// This is what the REPL shows.
+    pub fn repr(&self) -> String {
+        match self {
+            Self::String(string, ..) => format!("{:?}", string),
+            Self::BigInt(integer, ..) => format!("{}n", integer),
+            Self::Decimal(decimal, ..) => format!("{}d", decimal),
+            Self::Array(array, ..) => format!(
+                "[{}]",
+                array
+                    .iter()
+                    .map(Object::repr)
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+            Self::Map(map, ..) => format!(
+                "{{{}}}",
+                map.iter()
+                    .map(|(key, value)| format!("{:?}: {}", key, value.repr()))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+            object => object.to_string(),
+        }
+    }
+
     pub fn meta(&self) -> &Meta {
         match self {
+            Self::Integer(_, meta) => meta,
             Self::Number(_, meta) => meta,
+            Self::BigInt(_, meta) => meta,
+            Self::Decimal(_, meta) => meta,
             Self::String(_, meta) => meta,
             Self::Boolean(_, meta) => meta,
             Self::Array(_, meta) => meta,
+            Self::Map(_, meta) => meta,
+            Self::Function(_, meta) => meta,
             Self::Nil(meta) => meta,
         }
     }
 
-    pub fn set_return(&mut self) {
+    fn meta_mut(&mut self) -> &mut Meta {
         match self {
-            Self::Number(_, meta) => meta.is_return = true,
-            Self::String(_, meta) => meta.is_return = true,
-            Self::Boolean(_, meta) => meta.is_return = true,
-            Self::Array(_, meta) => meta.is_return = true,
-            Self::Nil(meta) => meta.is_return = true,
+            Self::Integer(_, meta) => meta,
+            Self::Number(_, meta) => meta,
+            Self::BigInt(_, meta) => meta,
+            Self::Decimal(_, meta) => meta,
+            Self::String(_, meta) => meta,
+            Self::Boolean(_, meta) => meta,
+            Self::Array(_, meta) => meta,
+            Self::Map(_, meta) => meta,
+            Self::Function(_, meta) => meta,
+            Self::Nil(meta) => meta,
         }
     }
 
+    pub fn set_return(&mut self) {
+        self.meta_mut().is_return = true;
+    }
+
     pub fn is_return(&self) -> bool {
-        match self {
-            Self::Number(_, meta) => meta.is_return,
-            Self::String(_, meta) => meta.is_return,
-            Self::Boolean(_, meta) => meta.is_return,
-            Self::Array(_, meta) => meta.is_return,
-            Self::Nil(meta) => meta.is_return,
-        }
+        self.meta().is_return
+    }
+
+    pub fn set_break(&mut self) {
+        self.meta_mut().is_break = true;
+    }
+
+    pub fn is_break(&self) -> bool {
+        self.meta().is_break
+    }
+
+    pub fn set_continue(&mut self) {
+        self.meta_mut().is_continue = true;
+    }
+
+    pub fn is_continue(&self) -> bool {
+        self.meta().is_continue
+    }
+
+    /// Returns true if the object is carrying a `return`, `break` or `continue` out of a block.
+    pub fn is_interrupt(&self) -> bool {
+        self.is_return() || self.is_break() || self.is_continue()
+    }
+
+    /// Clears every control flow flag, so the object can be used as a plain value again.
+    pub fn clear_interrupt(&mut self) {
+        *self.meta_mut() = Meta::default();
     }
 
     pub fn is_true(&self) -> bool {
@@ -74,7 +407,12 @@ impl Object {
             Self::Boolean(boolean, ..) => *boolean,
             Self::Nil(..) => false,
             Self::Array(array, ..) => !array.is_empty(),
+            Self::Map(map, ..) => !map.is_empty(),
+            Self::Function(..) => true,
+            Self::Integer(integer, ..) => *integer != 0,
             Self::Number(number, ..) => *number != 0.0,
+            Self::BigInt(integer, ..) => !integer.is_zero(),
+            Self::Decimal(decimal, ..) => !decimal.is_zero(),
             Self::String(string, ..) => !string.is_empty(),
         }
     }
@@ -106,4 +444,164 @@ impl Object {
             )),
         }
     }
+
+    pub fn index(&self, index: &Object, position: Position) -> Result<Object, Error> {
+        match self {
+            Object::Array(array, ..) => {
+                let offset = Self::element_offset(index, array.len(), &position)?;
+                Ok(array[offset].clone())
+            }
+            Object::String(string, ..) => {
+                let characters: Vec<char> = string.chars().collect();
+                let offset = Self::element_offset(index, characters.len(), &position)?;
+                Ok(Object::String(
+                    characters[offset].to_string(),
+                    Meta::default(),
+                ))
+            }
+            Object::Map(map, ..) => {
+                let key = Self::key(index, &position)?;
+                map.get(&key).cloned().ok_or_else(|| {
+                    Error::new(
+                        ErrorType::RuntimeError,
+                        format!("Key `{}` doesn't exist", key),
+                        position,
+                    )
+                })
+            }
+            _ => Err(Error::new(
+                ErrorType::RuntimeError,
+                format!("`{}` can't be indexed", self),
+                position,
+            )),
+        }
+    }
+
+    pub fn slice(
+        &self,
+        start: &Object,
+        end: &Object,
+        inclusive: bool,
+        position: Position,
+    ) -> Result<Object, Error> {
+        let length = match self {
+            Object::Array(array, ..) => array.len(),
+            Object::String(string, ..) => string.chars().count(),
+            _ => {
+                return Err(Error::new(
+                    ErrorType::RuntimeError,
+                    format!("`{}` can't be sliced", self),
+                    position,
+                ))
+            }
+        };
+        let start_index = Self::whole_number(start, &position)?;
+        let end_index = Self::whole_number(end, &position)?;
+        let from = Self::from_end(start_index, length);
+        let to = Self::from_end(end_index, length);
+        // An inclusive end of `i64::MAX` is out of bounds anyway, so the overflow is one too.
+        let to = if inclusive {
+            to.checked_add(1)
+        } else {
+            Some(to)
+        };
+        let to = match to {
+            Some(to) if from >= 0 && to <= length as i64 && from <= to => to,
+            _ => {
+                return Err(Error::new(
+                    ErrorType::RuntimeError,
+                    format!(
+                        "Slice `{}..{}{}` is out of bounds for length {}",
+                        start_index,
+                        if inclusive { "=" } else { "" },
+                        end_index,
+                        length
+                    ),
+                    position,
+                ))
+            }
+        };
+
+        let (from, to) = (from as usize, to as usize);
+        match self {
+            Object::Array(array, ..) => {
+                Ok(Object::Array(array[from..to].to_vec(), Meta::default()))
+            }
+            Object::String(string, ..) => Ok(Object::String(
+                string.chars().skip(from).take(to - from).collect(),
+                Meta::default(),
+            )),
+            _ => unreachable!(),
+        }
+    }
+
+    pub fn set_index(
+        &mut self,
+        index: &Object,
+        value: Object,
+        position: Position,
+    ) -> Result<(), Error> {
+        match self {
+            Object::Array(array, ..) => {
+                let offset = Self::element_offset(index, array.len(), &position)?;
+                array[offset] = value;
+                Ok(())
+            }
+            Object::Map(map, ..) => {
+                map.insert(Self::key(index, &position)?, value);
+                Ok(())
+            }
+            _ => Err(Error::new(
+                ErrorType::RuntimeError,
+                format!("`{}` does not support index assignment", self),
+                position,
+            )),
+        }
+    }
+
+    pub fn key(key: &Object, position: &Position) -> Result<String, Error> {
+        match key {
+            Object::String(string, ..) => Ok(string.clone()),
+            _ => Err(Error::new(
+                ErrorType::RuntimeError,
+                format!("Map keys must be strings, got `{}`", key),
+                position.clone(),
+            )),
+        }
+    }
+
+    fn whole_number(index: &Object, position: &Position) -> Result<i64, Error> {
+        match index {
+            Object::Integer(integer, ..) => Ok(*integer),
+            Object::Number(number, ..) if number.fract() == 0. => Ok(*number as i64),
+            _ => Err(Error::new(
+                ErrorType::RuntimeError,
+                format!("Index must be a whole number, got `{}`", index),
+                position.clone(),
+            )),
+        }
+    }
+
+    // Negative indices count from the end, `-1` is the last element.
+    fn from_end(index: i64, length: usize) -> i64 {
+        if index < 0 {
+            length as i64 + index
+        } else {
+            index
+        }
+    }
+
+    fn element_offset(index: &Object, length: usize, position: &Position) -> Result<usize, Error> {
+        let index = Self::whole_number(index, position)?;
+        let offset = Self::from_end(index, length);
+        if offset < 0 || offset >= length as i64 {
+            Err(Error::new(
+                ErrorType::RuntimeError,
+                format!("Index `{}` is out of bounds for length {}", index, length),
+                position.clone(),
+            ))
+        } else {
+            Ok(offset as usize)
+        }
+    }
 }
@@ -1,11 +1,79 @@
-#[derive(Debug, Clone)]
+use std::{fmt, rc::Rc};
+
+/// A range of bytes in the source, the end is exclusive.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+}
+
+impl Span {
+    pub fn new(start: usize, end: usize) -> Self {
+        Self { start, end }
+    }
+}
+
+/// Where something is in the source. The row and the column start at 1, positions that don't point
+/// into any source (e.g. the builtin functions) have no source text.
+#[derive(Clone, Default)]
 pub struct Position {
-    pub source_path: String,
+    pub source_path: Rc<str>,
     pub row: usize,
+    pub column: usize,
+    pub span: Span,
+    pub source: Option<Rc<str>>,
 }
 
 impl Position {
     pub fn new(source_path: String, row: usize) -> Self {
-        Self { source_path, row }
+        Self {
+            source_path: Rc::from(source_path),
+            row,
+            ..Default::default()
+        }
+    }
+
+    /// Creates a position that covers both positions, starting at this one.
+    pub fn to(&self, other: &Position) -> Position {
+        let mut position = self.clone();
+        position.span.end = self.span.end.max(other.span.end);
+        position
+    }
+
+    /// Returns the source text the position covers.
+    pub fn text(&self) -> Option<&str> {
+        self.source.as_deref()?.get(self.span.start..self.span.end)
+    }
+
+    /// Returns the line the position starts at, and the span relative to that line.
+    pub fn line(&self) -> Option<(&str, Span)> {
+        let source = self.source.as_deref()?;
+        let start = self.span.start.min(source.len());
+        let line_start = source[..start].rfind('\n').map_or(0, |index| index + 1);
+        let line_end = source[start..]
+            .find('\n')
+            .map_or(source.len(), |index| start + index);
+        let end = self.span.end.clamp(start, line_end);
+        let line = source[line_start..line_end].trim_end_matches('\r');
+        Some((line, Span::new(start - line_start, end - line_start)))
+    }
+}
+
+// The source text is left out, it would fill the whole output.
+impl fmt::Debug for Position {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}:{}", self.source_path, self.row, self.column)
+    }
+}
+
+impl fmt::Display for Position {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.row == 0 {
+            write!(f, "{}", self.source_path)
+        } else if self.column == 0 {
+            write!(f, "{}:{}", self.source_path, self.row)
+        } else {
+            write!(f, "{}:{}:{}", self.source_path, self.row, self.column)
+        }
     }
 }
@@ -7,6 +7,8 @@ pub enum TokenType {
     Identifier,
     Number,
     String,
+    // The part of a string before an embedded expression, e.g. `"a ${`.
+    Interpolation,
     Boolean,
     Nil,
 
@@ -28,6 +30,10 @@ pub enum TokenType {
     Or,
 
     Comma,
+    Colon,
+    DotDot,
+    DotDotEqual,
+    DotDotDot,
 
     OpenParen,
     CloseParen,
@@ -41,6 +47,14 @@ pub enum TokenType {
     If,
     Else,
     Return,
+    While,
+    For,
+    In,
+    Break,
+    Continue,
+
+    // A `///` comment, it's text is the literal. It documents the function that follows it.
+    DocComment,
 
     EOF,
 }
@@ -51,6 +65,7 @@ impl Display for TokenType {
             Self::Identifier => write!(f, "identifier"),
             Self::Number => write!(f, "number"),
             Self::String => write!(f, "string"),
+            Self::Interpolation => write!(f, "interpolated string"),
             Self::Boolean => write!(f, "boolean"),
             Self::Nil => write!(f, "nil"),
 
@@ -72,6 +87,10 @@ impl Display for TokenType {
             Self::Or => write!(f, "||"),
 
             Self::Comma => write!(f, ","),
+            Self::Colon => write!(f, ":"),
+            Self::DotDot => write!(f, ".."),
+            Self::DotDotEqual => write!(f, "..="),
+            Self::DotDotDot => write!(f, "..."),
 
             Self::OpenParen => write!(f, "("),
             Self::CloseParen => write!(f, ")"),
@@ -85,8 +104,15 @@ impl Display for TokenType {
             Self::If => write!(f, "if"),
             Self::Else => write!(f, "else"),
             Self::Return => write!(f, "return"),
+            Self::While => write!(f, "while"),
+            Self::For => write!(f, "for"),
+            Self::In => write!(f, "in"),
+            Self::Break => write!(f, "break"),
+            Self::Continue => write!(f, "continue"),
+
+            Self::DocComment => write!(f, "doc comment"),
 
-            Self::EOF => write!(f, "\0"),
+            Self::EOF => write!(f, "end of file"),
         }
     }
 }
@@ -1,49 +1,83 @@
-use std::collections::HashMap;
+use std::{collections::HashMap, rc::Rc};
 
 use crate::common::{
     error::{Error, ErrorType},
+    number::{BigInt, Decimal},
     object::{Meta, Object},
-    position::Position,
+    position::{Position, Span},
     token::{Token, TokenType},
 };
 
 pub struct Lexer {
     source: Vec<char>,
+    // The byte offset of every character, and of the end of the source.
+    offsets: Vec<usize>,
 
     keywords: HashMap<String, TokenType>,
 
     start: usize,
     current: usize,
 
+    // Where the current line and the line of the current token start.
+    line_start: usize,
+    start_line: usize,
+    start_row: usize,
+
     current_position: Position,
+
+    // The open curly braces of every embedded expression that is being lexed, and where the
+    // expression starts. The string goes on at the `}` that closes it.
+    interpolations: Vec<(usize, Position)>,
 }
 
 impl Lexer {
     pub fn new(source_path: String, source: &str) -> Self {
+        let mut offsets: Vec<usize> = source.char_indices().map(|(offset, _)| offset).collect();
+        offsets.push(source.len());
+        let mut current_position = Position::new(source_path, 1);
+        current_position.source = Some(Rc::from(source));
+
         Self {
             source: source.chars().collect(),
+            offsets,
 
             keywords: HashMap::new(),
 
             start: 0,
             current: 0,
 
-            current_position: Position::new(source_path, 1),
+            line_start: 0,
+            start_line: 0,
+            start_row: 1,
+
+            current_position,
+
+            interpolations: Vec::new(),
         }
     }
 
+    /// Returns the keywords of the language, with the type of token each one is lexed as.
+    pub fn keywords() -> HashMap<String, TokenType> {
+        let mut keywords = HashMap::new();
+        keywords.insert("let".to_string(), TokenType::Let);
+        keywords.insert("func".to_string(), TokenType::Func);
+        keywords.insert("if".to_string(), TokenType::If);
+        keywords.insert("else".to_string(), TokenType::Else);
+        keywords.insert("return".to_string(), TokenType::Return);
+        keywords.insert("while".to_string(), TokenType::While);
+        keywords.insert("for".to_string(), TokenType::For);
+        keywords.insert("in".to_string(), TokenType::In);
+        keywords.insert("break".to_string(), TokenType::Break);
+        keywords.insert("continue".to_string(), TokenType::Continue);
+
+        keywords.insert("true".to_string(), TokenType::Boolean);
+        keywords.insert("false".to_string(), TokenType::Boolean);
+        keywords.insert("nil".to_string(), TokenType::Nil);
+        keywords
+    }
+
     fn init_keywords(&mut self) {
-        self.keywords.insert("let".to_string(), TokenType::Let);
-        self.keywords.insert("func".to_string(), TokenType::Func);
-        self.keywords.insert("if".to_string(), TokenType::If);
-        self.keywords.insert("else".to_string(), TokenType::Else);
-        self.keywords
-            .insert("return".to_string(), TokenType::Return);
-
-        self.keywords.insert("true".to_string(), TokenType::Boolean);
-        self.keywords
-            .insert("false".to_string(), TokenType::Boolean);
-        self.keywords.insert("nil".to_string(), TokenType::Nil);
+        self.keywords = Self::keywords();
     }
 
     pub fn lex(&mut self) -> Result<Vec<Token>, Error> {
@@ -52,12 +86,22 @@ impl Lexer {
         let mut tokens = Vec::new();
 
         while !self.eof() {
-            self.start = self.current;
+            self.begin_token();
             if let Some(token) = self.next_token()? {
                 tokens.push(token);
             }
         }
 
+        if let Some((_, position)) = self.interpolations.pop() {
+            return Err(Error::new(
+                ErrorType::LexingError,
+                "Unterminated interpolation".to_owned(),
+                position,
+            )
+            .with_help("close the embedded expression with a `}`".to_owned()));
+        }
+
+        self.begin_token();
         tokens.push(self.token(TokenType::EOF, None));
         Ok(tokens)
     }
@@ -75,15 +119,51 @@ impl Lexer {
         }
     }
 
+    fn peek_next(&self) -> char {
+        if self.current + 1 < self.source.len() {
+            self.source[self.current + 1]
+        } else {
+            '\0'
+        }
+    }
+
     fn advance(&mut self) {
         if !self.eof() {
             self.current += 1;
         }
     }
 
+    fn begin_token(&mut self) {
+        self.start = self.current;
+        self.start_line = self.line_start;
+        self.start_row = self.current_position.row;
+    }
+
+    /// Returns the position of the token that's being lexed.
+    fn position(&self) -> Position {
+        let mut position = self.current_position.clone();
+        position.row = self.start_row;
+        position.column = self.start - self.start_line + 1;
+        position.span = Span::new(self.offsets[self.start], self.offsets[self.current]);
+        position
+    }
+
+    /// Returns the position from the character at `start` to the current one, on the current line.
+    fn position_from(&self, start: usize) -> Position {
+        let mut position = self.current_position.clone();
+        position.column = start - self.line_start + 1;
+        position.span = Span::new(self.offsets[start], self.offsets[self.current]);
+        position
+    }
+
+    fn newline(&mut self) {
+        self.current_position.row += 1;
+        self.line_start = self.current;
+    }
+
     fn token(&mut self, ttype: TokenType, literal: Option<Object>) -> Token {
         let lexeme: String = self.source[self.start..self.current].iter().collect();
-        Token::new(ttype, lexeme, literal, self.current_position.clone())
+        Token::new(ttype, lexeme, literal, self.position())
     }
 
     fn next_token(&mut self) -> Result<Option<Token>, Error> {
@@ -102,6 +182,8 @@ impl Lexer {
 
             ',' => Ok(Some(self.token(TokenType::Comma, None))),
 
+            ':' => Ok(Some(self.token(TokenType::Colon, None))),
+
             '(' => Ok(Some(self.token(TokenType::OpenParen, None))),
 
             ')' => Ok(Some(self.token(TokenType::CloseParen, None))),
@@ -110,9 +192,24 @@ impl Lexer {
 
             ']' => Ok(Some(self.token(TokenType::CloseBrack, None))),
 
-            '{' => Ok(Some(self.token(TokenType::OpenCurly, None))),
+            '{' => {
+                if let Some((depth, _)) = self.interpolations.last_mut() {
+                    *depth += 1;
+                }
+                Ok(Some(self.token(TokenType::OpenCurly, None)))
+            }
 
-            '}' => Ok(Some(self.token(TokenType::CloseCurly, None))),
+            '}' => match self.interpolations.last_mut() {
+                Some((0, _)) => {
+                    self.interpolations.pop();
+                    self.make_string()
+                }
+                Some((depth, _)) => {
+                    *depth -= 1;
+                    Ok(Some(self.token(TokenType::CloseCurly, None)))
+                }
+                None => Ok(Some(self.token(TokenType::CloseCurly, None))),
+            },
 
             '\0' => Ok(Some(self.token(TokenType::EOF, None))),
 
@@ -120,15 +217,45 @@ impl Lexer {
 
             '\n' => self.count_newline(),
 
-            '/' => {
-                if self.peek() == '/' {
+            '.' => {
+                if self.peek() == '.' {
                     self.advance();
-                    self.ignore_comment()
+                    if self.peek() == '=' {
+                        self.advance();
+                        Ok(Some(self.token(TokenType::DotDotEqual, None)))
+                    } else if self.peek() == '.' {
+                        self.advance();
+                        Ok(Some(self.token(TokenType::DotDotDot, None)))
+                    } else {
+                        Ok(Some(self.token(TokenType::DotDot, None)))
+                    }
                 } else {
-                    Ok(Some(self.token(TokenType::Slash, None)))
+                    Err(Error::new(
+                        ErrorType::LexingError,
+                        format!("Unexpected charected `{}`", current_char),
+                        self.position(),
+                    ))
                 }
             }
 
+            '/' => match self.peek() {
+                '/' => {
+                    self.advance();
+                    // `////` and longer are plain comments, e.g. a line of slashes.
+                    if self.peek() == '/' && self.peek_next() != '/' {
+                        self.advance();
+                        self.make_doc_comment()
+                    } else {
+                        self.ignore_comment()
+                    }
+                }
+                '*' => {
+                    self.advance();
+                    self.ignore_block_comment()
+                }
+                _ => Ok(Some(self.token(TokenType::Slash, None))),
+            },
+
             '=' => {
                 if self.peek() == '=' {
                     self.advance();
@@ -173,7 +300,7 @@ impl Lexer {
                     Err(Error::new(
                         ErrorType::LexingError,
                         format!("Unexpected charected `{}`", current_char),
-                        self.current_position.clone(),
+                        self.position(),
                     ))
                 }
             }
@@ -186,7 +313,7 @@ impl Lexer {
                     Err(Error::new(
                         ErrorType::LexingError,
                         format!("Unexpected charected `{}`", current_char),
-                        self.current_position.clone(),
+                        self.position(),
                     ))
                 }
             }
@@ -200,7 +327,7 @@ impl Lexer {
                     Err(Error::new(
                         ErrorType::LexingError,
                         format!("Unexpected charected `{}`", current_char),
-                        self.current_position.clone(),
+                        self.position(),
                     ))
                 }
             }
@@ -208,7 +335,7 @@ impl Lexer {
     }
 
     fn count_newline(&mut self) -> Result<Option<Token>, Error> {
-        self.current_position.row += 1;
+        self.newline();
         Ok(None)
     }
 
@@ -219,42 +346,249 @@ impl Lexer {
         Ok(None)
     }
 
+    fn make_doc_comment(&mut self) -> Result<Option<Token>, Error> {
+        self.ignore_comment()?;
+        let text: String = self.source[self.start + 3..self.current].iter().collect();
+        let text = text
+            .strip_prefix(' ')
+            .unwrap_or(&text)
+            .trim_end()
+            .to_string();
+        Ok(Some(self.token(
+            TokenType::DocComment,
+            Some(Object::String(text, Meta::default())),
+        )))
+    }
+
+    // Block comments can be nested, e.g. `/* a /* b */ c */` is a single comment.
+    fn ignore_block_comment(&mut self) -> Result<Option<Token>, Error> {
+        let mut depth = 1;
+        while !self.eof() {
+            let current_char = self.peek();
+            self.advance();
+            match current_char {
+                '/' if self.peek() == '*' => {
+                    self.advance();
+                    depth += 1;
+                }
+                '*' if self.peek() == '/' => {
+                    self.advance();
+                    depth -= 1;
+                    if depth == 0 {
+                        return Ok(None);
+                    }
+                }
+                '\n' => self.newline(),
+                _ => {}
+            }
+        }
+        Err(Error::new(
+            ErrorType::LexingError,
+            "Unterminated comment".to_owned(),
+            self.position(),
+        )
+        .with_help("close the comment with a `*/`".to_owned()))
+    }
+
+    // Lexes a string from it's opening `"`, or from the `}` that ends an embedded expression.
+    // The string ends at the closing `"`, or at the `${` of the next embedded expression.
     fn make_string(&mut self) -> Result<Option<Token>, Error> {
-        loop {
-            if self.peek() == '"' || self.peek() == '\0' {
-                break;
+        let mut value = String::new();
+        while !self.eof() {
+            match self.peek() {
+                '"' => {
+                    self.advance();
+                    return Ok(Some(self.token(
+                        TokenType::String,
+                        Some(Object::String(value, Meta::default())),
+                    )));
+                }
+                '$' if self.peek_next() == '{' => {
+                    self.advance();
+                    self.advance();
+                    let position = self.position();
+                    self.interpolations.push((0, position));
+                    return Ok(Some(self.token(
+                        TokenType::Interpolation,
+                        Some(Object::String(value, Meta::default())),
+                    )));
+                }
+                '\\' => value.push(self.make_escape()?),
+                character => {
+                    self.advance();
+                    if character == '\n' {
+                        self.newline();
+                    }
+                    value.push(character);
+                }
             }
-            self.advance();
         }
-        if self.peek() == '"' {
-            self.advance();
-            let lexeme: String = self.source[self.start + 1..self.current - 1]
-                .iter()
-                .collect();
-            Ok(Some(self.token(
-                TokenType::String,
-                Some(Object::String(lexeme, Meta::default())),
-            )))
-        } else {
-            Err(Error::new(
+        Err(self.unterminated_string())
+    }
+
+    fn unterminated_string(&self) -> Error {
+        Error::new(
+            ErrorType::LexingError,
+            "Unterminated string".to_owned(),
+            self.position(),
+        )
+        .with_help("close the string with a `\"`".to_owned())
+    }
+
+    fn make_escape(&mut self) -> Result<char, Error> {
+        let start = self.current;
+        self.advance();
+        if self.eof() {
+            return Err(self.unterminated_string());
+        }
+        let escaped = self.peek();
+        self.advance();
+        match escaped {
+            '"' => Ok('"'),
+            '\\' => Ok('\\'),
+            '$' => Ok('$'),
+            'n' => Ok('\n'),
+            't' => Ok('\t'),
+            'r' => Ok('\r'),
+            '0' => Ok('\0'),
+            'u' => {
+                let mut digits = String::new();
+                if self.peek() == '{' {
+                    self.advance();
+                    while self.peek().is_ascii_hexdigit() {
+                        digits.push(self.peek());
+                        self.advance();
+                    }
+                }
+                let closed = self.peek() == '}';
+                if closed {
+                    self.advance();
+                }
+                u32::from_str_radix(&digits, 16)
+                    .ok()
+                    .filter(|_| closed && digits.len() <= 6)
+                    .and_then(char::from_u32)
+                    .ok_or_else(|| {
+                        Error::new(
+                            ErrorType::LexingError,
+                            "Invalid unicode escape".to_owned(),
+                            self.position_from(start),
+                        )
+                        .with_help("write the code point in hex, e.g. `\\u{1F600}`".to_owned())
+                    })
+            }
+            _ => Err(Error::new(
                 ErrorType::LexingError,
-                "Unterminated string".to_owned(),
-                self.current_position.clone(),
-            ))
+                format!("Unknown escape sequence `\\{}`", escaped),
+                self.position_from(start),
+            )
+            .with_note(
+                "the escapes are `\\\"`, `\\\\`, `\\$`, `\\n`, `\\t`, `\\r`, `\\0` and `\\u{...}`"
+                    .to_owned(),
+            )),
         }
     }
 
     fn make_number(&mut self) -> Result<Option<Token>, Error> {
-        while self.peek().is_ascii_digit() {
+        // A `0x`, `0o` or `0b` prefix makes an integer in another base, e.g. `0xFF`.
+        let radix = match (self.source[self.start], self.peek()) {
+            ('0', 'x') => Some((16, "hexadecimal")),
+            ('0', 'o') => Some((8, "octal")),
+            ('0', 'b') => Some((2, "binary")),
+            _ => None,
+        };
+        if let Some((radix, name)) = radix {
+            self.advance();
+            return self.make_radix_number(radix, name);
+        }
+
+        self.digits(10)?;
+        // A dot that isn't followed by a digit belongs to a range, e.g. `1..5`.
+        let is_float = self.peek() == '.' && self.peek_next().is_ascii_digit();
+        if is_float {
             self.advance();
+            self.digits(10)?;
         }
-        if self.peek() == '.' {
+        let has_exponent = matches!(self.peek(), 'e' | 'E');
+        if has_exponent {
             self.advance();
-            while self.peek().is_ascii_digit() {
+            if matches!(self.peek(), '+' | '-') {
                 self.advance();
             }
+            if !self.peek().is_ascii_digit() {
+                let lexeme: String = self.source[self.start..self.current].iter().collect();
+                return Err(Error::new(
+                    ErrorType::LexingError,
+                    format!("Expected the digits of the exponent after `{}`", lexeme),
+                    self.position(),
+                )
+                .with_help("write the exponent after the `e`, e.g. `1.5e-3`".to_owned()));
+            }
+            self.digits(10)?;
+        }
+        // The token keeps the number as it's written, the `_` are only dropped to parse it.
+        let lexeme: String = self.source[self.start..self.current]
+            .iter()
+            .filter(|c| **c != '_')
+            .collect();
+
+        // The `n` and `d` suffixes make exact numbers, e.g. `123n` and `1.10d`.
+        let suffix = self.peek();
+        let next = self.peek_next();
+        if matches!(suffix, 'n' | 'd') && !(next.is_ascii_alphanumeric() || next == '_') {
+            self.advance();
+            if has_exponent {
+                return Err(Error::new(
+                    ErrorType::LexingError,
+                    format!(
+                        "The exact number `{}{}` can't have an exponent",
+                        lexeme, suffix
+                    ),
+                    self.position(),
+                )
+                .with_help("write all of it's digits, e.g. `1500d` for `1.5e3d`".to_owned()));
+            }
+            let object =
+                match suffix {
+                    'n' if is_float => {
+                        return Err(Error::new(
+                            ErrorType::LexingError,
+                            format!("The big integer `{}n` can't have a fraction", lexeme),
+                            self.position(),
+                        )
+                        .with_help(format!(
+                            "use the `d` suffix for an exact decimal, e.g. `{}d`",
+                            lexeme
+                        )))
+                    }
+                    'n' => BigInt::parse(&lexeme)
+                        .map(|integer| Object::BigInt(integer, Meta::default())),
+                    _ => Decimal::parse(&lexeme)
+                        .map(|decimal| Object::Decimal(decimal, Meta::default())),
+                };
+            return match object {
+                Some(object) => Ok(Some(self.token(TokenType::Number, Some(object)))),
+                None => Err(Error::new(
+                    ErrorType::LexingError,
+                    format!("could not parse {}{}", lexeme, suffix),
+                    self.position(),
+                )),
+            };
+        }
+        self.reject_suffix(&lexeme)?;
+
+        if !is_float && !has_exponent {
+            // The digits of the smallest integer are too large on their own, the token gets their
+            // negation and the parser checks that they are negated, e.g. `-9223372036854775808`.
+            let integer = lexeme.parse().or_else(|_| format!("-{}", lexeme).parse());
+            return match integer {
+                Ok(integer) => Ok(Some(self.token(
+                    TokenType::Number,
+                    Some(Object::Integer(integer, Meta::default())),
+                ))),
+                Err(_) => Err(too_large(&lexeme, self.position())),
+            };
         }
-        let lexeme: String = self.source[self.start..self.current].iter().collect();
         if let Ok(float) = lexeme.parse() {
             Ok(Some(self.token(
                 TokenType::Number,
@@ -264,25 +598,128 @@ impl Lexer {
             Err(Error::new(
                 ErrorType::LexingError,
                 format!("could not parse {} to float", lexeme),
-                self.current_position.clone(),
+                self.position(),
             ))
         }
     }
 
+    // The prefix is already consumed, only big integers can have a suffix here, e.g. `0xFFn`.
+    fn make_radix_number(&mut self, radix: u32, name: &str) -> Result<Option<Token>, Error> {
+        self.digits(radix)?;
+        let digits: String = self.source[self.start + 2..self.current]
+            .iter()
+            .filter(|c| **c != '_')
+            .collect();
+        let is_suffix = self.peek() == 'n' && !self.is_word(self.peek_next());
+        if self.peek().is_ascii_alphanumeric() && !is_suffix {
+            let digit = self.current;
+            self.advance();
+            return Err(Error::new(
+                ErrorType::LexingError,
+                format!(
+                    "Invalid digit `{}` in a {} number",
+                    self.source[digit], name
+                ),
+                self.position_from(digit),
+            )
+            .with_note(format!(
+                "{} numbers only have the digits `{}`",
+                name,
+                digit_range(radix)
+            )));
+        }
+        if digits.is_empty() {
+            let prefix: String = self.source[self.start..self.current].iter().collect();
+            return Err(Error::new(
+                ErrorType::LexingError,
+                format!("Expected {} digits after `{}`", name, prefix),
+                self.position(),
+            )
+            .with_note(format!(
+                "{} numbers only have the digits `{}`",
+                name,
+                digit_range(radix)
+            )));
+        }
+
+        if self.peek() == 'n' {
+            self.advance();
+            let integer = BigInt::parse_radix(&digits, radix).unwrap_or_else(BigInt::zero);
+            return Ok(Some(self.token(
+                TokenType::Number,
+                Some(Object::BigInt(integer, Meta::default())),
+            )));
+        }
+        let integer = i64::from_str_radix(&digits, radix)
+            .or_else(|_| i64::from_str_radix(&format!("-{}", digits), radix));
+        match integer {
+            Ok(integer) => Ok(Some(self.token(
+                TokenType::Number,
+                Some(Object::Integer(integer, Meta::default())),
+            ))),
+            Err(_) => {
+                let lexeme: String = self.source[self.start..self.current].iter().collect();
+                Err(too_large(&lexeme, self.position()))
+            }
+        }
+    }
+
+    // Consumes the digits of a number in the given base, an `_` can separate them, e.g. `1_000`.
+    fn digits(&mut self, radix: u32) -> Result<(), Error> {
+        while self.peek().is_digit(radix) || self.peek() == '_' {
+            if self.peek() == '_' && !self.peek_next().is_digit(radix) {
+                let underscore = self.current;
+                self.advance();
+                return Err(Error::new(
+                    ErrorType::LexingError,
+                    "Expected a digit after `_`".to_owned(),
+                    self.position_from(underscore),
+                )
+                .with_help("an `_` only separates digits, e.g. `1_000_000`".to_owned()));
+            }
+            self.advance();
+        }
+        Ok(())
+    }
+
+    // A number that runs into letters, e.g. `12px`, is a typo rather than a number and a name.
+    fn reject_suffix(&mut self, lexeme: &str) -> Result<(), Error> {
+        if !self.is_word(self.peek()) {
+            return Ok(());
+        }
+        let start = self.current;
+        while self.is_word(self.peek()) {
+            self.advance();
+        }
+        let suffix: String = self.source[start..self.current].iter().collect();
+        Err(Error::new(
+            ErrorType::LexingError,
+            format!("Invalid suffix `{}` for the number `{}`", suffix, lexeme),
+            self.position_from(start),
+        )
+        .with_note("the suffixes are `n` for big integers and `d` for decimals".to_owned()))
+    }
+
+    fn is_word(&self, c: char) -> bool {
+        c.is_ascii_alphanumeric() || c == '_'
+    }
+
     fn make_identifier(&mut self) -> Result<Option<Token>, Error> {
-        while self.peek().is_ascii_alphanumeric() || self.peek() == '_' {
+        while self.is_word(self.peek()) {
             self.advance();
         }
         let lexeme: String = self.source[self.start..self.current].iter().collect();
         if let Some(ttype) = self.keywords.get(&lexeme) {
             if ttype == &TokenType::Boolean && lexeme == "true" {
-                Ok(Some(
-                    self.token(TokenType::Boolean, Some(Object::Boolean(true, Meta::default()))),
-                ))
+                Ok(Some(self.token(
+                    TokenType::Boolean,
+                    Some(Object::Boolean(true, Meta::default())),
+                )))
             } else if ttype == &TokenType::Boolean && lexeme == "false" {
-                Ok(Some(
-                    self.token(TokenType::Boolean, Some(Object::Boolean(false, Meta::default()))),
-                ))
+                Ok(Some(self.token(
+                    TokenType::Boolean,
+                    Some(Object::Boolean(false, Meta::default())),
+                )))
             } else {
                 Ok(Some(self.token(ttype.clone(), None)))
             }
@@ -291,3 +728,26 @@ impl Lexer {
         }
     }
 }
+
+/// The error of an integer literal that doesn't fit in 64 bits.
+pub fn too_large(lexeme: &str, position: Position) -> Error {
+    Error::new(
+        ErrorType::LexingError,
+        format!("The integer `{}` is too large", lexeme),
+        position,
+    )
+    .with_note(format!("integers are at most {}", i64::MAX))
+    .with_help(format!(
+        "add the `n` suffix to make it a big integer, e.g. `{}n`",
+        lexeme
+    ))
+}
+
+// The digits of a base, for the notes of the errors.
+fn digit_range(radix: u32) -> &'static str {
+    match radix {
+        2 => "0` and `1",
+        8 => "0` to `7",
+        _ => "0` to `9` and `a` to `f",
+    }
+}
@@ -1,42 +1,136 @@
+use std::collections::HashMap;
+
 use crate::common::{
     ast::{
         ArrayExpression, AssignmentStatement, BinaryExpression, BlockExpression, CallExpression,
-        ElseBlock, Expression, FunctionStatement, GroupExpression, IdentifierExpression,
-        IfExpression, LetStatement, LiteralExpression, Parameter, Program, Statement,
-        UnaryExpression,
+        ElseBlock, Expression, ForStatement, FunctionStatement, GroupExpression,
+        IdentifierExpression, IfExpression, IndexExpression, InterpolationExpression, LetStatement,
+        LiteralExpression, MapExpression, Parameter, Program, RangeExpression, Statement,
+        UnaryExpression, WhileStatement,
     },
-    error::{Error, ErrorType},
+    error::{Diagnostics, Error, ErrorType},
     object::{Meta, Object},
     token::{Token, TokenType},
 };
 
+use super::lexer::too_large;
+
 pub struct Parser {
     tokens: Vec<Token>,
     current: usize,
+    // How many loops are enclosing the current statement, `break` and `continue` are only valid inside one.
+    loop_depth: usize,
+    // The errors of the statements that couldn't be parsed, the parser keeps going after each one.
+    errors: Vec<Error>,
+    // The doc comments by the index of the token that follows them.
+    docs: HashMap<usize, String>,
 }
 
 impl Parser {
     pub fn new(tokens: Vec<Token>) -> Self {
-        Self { tokens, current: 0 }
+        // Doc comments aren't part of the grammar, they're put aside for the function that follows them.
+        let mut docs = HashMap::new();
+        let mut doc = Vec::new();
+        let mut code = Vec::new();
+        for token in tokens {
+            if token.ttype == TokenType::DocComment {
+                if let Some(Object::String(line, ..)) = token.literal {
+                    doc.push(line);
+                }
+            } else {
+                if !doc.is_empty() {
+                    docs.insert(code.len(), doc.join("\n"));
+                    doc.clear();
+                }
+                code.push(token);
+            }
+        }
+
+        Self {
+            tokens: code,
+            current: 0,
+            loop_depth: 0,
+            errors: Vec::new(),
+            docs,
+        }
     }
 
-    pub fn parse(&mut self) -> Result<Program, Error> {
+    /// Parses the whole program, every syntax error is reported instead of only the first one.
+    pub fn parse(&mut self) -> Result<Program, Diagnostics> {
         let mut program = Vec::new();
 
         while !self.eof() {
-            program.push(self.statemet()?);
+            if let Some(statement) = self.recoverable_statement() {
+                program.push(statement);
+            }
+        }
+
+        if self.errors.is_empty() {
+            Ok(program)
+        } else {
+            Err(Diagnostics::new(std::mem::take(&mut self.errors)))
+        }
+    }
+
+    // Parses a statement, if it fails the error is kept and the tokens are skipped to the next statement.
+    fn recoverable_statement(&mut self) -> Option<Statement> {
+        let start = self.current;
+        match self.statemet() {
+            Ok(statement) => Some(statement),
+            Err(error) => {
+                // Whatever comes after the last token of the broken statement starts a new one.
+                let last = if self.current > start {
+                    self.current - 1
+                } else {
+                    self.current
+                };
+                let row = self.tokens[last].position.row;
+                self.errors.push(error);
+                self.synchronize(row);
+                if self.current == start && !self.eof() {
+                    self.advance();
+                }
+                None
+            }
         }
+    }
 
-        Ok(program)
+    // Skips to the start of the next statement: a keyword that starts one, the `}` of the current
+    // block, or the next line. Nested blocks are skipped as a whole.
+    fn synchronize(&mut self, row: usize) {
+        let mut depth = 0;
+        while !self.eof() {
+            let token = self.peek();
+            match token.ttype {
+                TokenType::OpenCurly => depth += 1,
+                TokenType::CloseCurly if depth == 0 => return,
+                TokenType::CloseCurly => depth -= 1,
+                TokenType::Let
+                | TokenType::Func
+                | TokenType::While
+                | TokenType::For
+                | TokenType::Return
+                | TokenType::Break
+                | TokenType::Continue
+                    if depth == 0 =>
+                {
+                    return
+                }
+                _ if depth == 0 && token.position.row > row => return,
+                _ => {}
+            }
+            self.advance();
+        }
     }
 
     fn peek(&self) -> Token {
         self.tokens[self.current].clone()
     }
 
-    // It can go wrong, so rebember where it's being used.
-    fn peek_next(&self) -> Token {
-        self.tokens[self.current + 1].clone()
+    // Looks ahead without going past the end of file.
+    fn peek_nth(&self, offset: usize) -> Token {
+        let index = (self.current + offset).min(self.tokens.len() - 1);
+        self.tokens[index].clone()
     }
 
     fn eof(&self) -> bool {
@@ -75,19 +169,26 @@ impl Parser {
 
     fn statemet(&mut self) -> Result<Statement, Error> {
         match self.peek().ttype {
-            TokenType::Func => Ok(Statement::Function(self.function_statement()?)),
+            TokenType::Func if self.peek_nth(1).ttype != TokenType::OpenParen => {
+                Ok(Statement::Function(self.function_statement()?))
+            }
             TokenType::Let => Ok(Statement::Let(self.let_statement()?)),
+            TokenType::While => Ok(Statement::While(self.while_statement()?)),
+            TokenType::For => Ok(Statement::For(self.for_statement()?)),
             TokenType::Return => Ok(Statement::Return(self.return_statement()?)),
-            TokenType::OpenCurly => Ok(Statement::Expression(Expression::Block(
-                self.block_expression()?,
-            ))),
-            current_ttype => {
-                if current_ttype == TokenType::Identifier
-                    && self.peek_next().ttype == TokenType::Equal
-                {
-                    Ok(Statement::Assignment(self.assignment_statement()?))
+            TokenType::Break => Ok(Statement::Break(self.loop_control_statement()?)),
+            TokenType::Continue => Ok(Statement::Continue(self.loop_control_statement()?)),
+            TokenType::OpenCurly if !self.is_map_literal() => Ok(Statement::Expression(
+                Expression::Block(self.block_expression()?),
+            )),
+            _ => {
+                let expression = self.expression()?;
+                if self.does_match(&[TokenType::Equal]) {
+                    Ok(Statement::Assignment(
+                        self.assignment_statement(expression)?,
+                    ))
                 } else {
-                    Ok(Statement::Expression(self.expression()?))
+                    Ok(Statement::Expression(expression))
                 }
             }
         }
@@ -113,22 +214,120 @@ impl Parser {
         }
     }
 
-    fn assignment_statement(&mut self) -> Result<AssignmentStatement, Error> {
-        let identifier = self.eat(TokenType::Identifier)?;
+    fn assignment_statement(&mut self, target: Expression) -> Result<AssignmentStatement, Error> {
         self.eat(TokenType::Equal)?;
         let expression = self.expression()?;
+        let position = target.position();
 
-        Ok(AssignmentStatement::new(identifier, expression))
+        // Unwind `arr[0][1]` into the variable and the indices from the outermost to the innermost.
+        let mut target = target;
+        let mut indices = Vec::new();
+        loop {
+            match target {
+                Expression::Identifier(identifier_expression) => {
+                    indices.reverse();
+                    return Ok(AssignmentStatement::new(
+                        identifier_expression.identifier,
+                        indices,
+                        expression,
+                    ));
+                }
+                Expression::Index(index_expression) => {
+                    indices.push(*index_expression.index);
+                    target = *index_expression.object;
+                }
+                _ => {
+                    return Err(Error::new(
+                        ErrorType::ParsingError,
+                        "Invalid assignment target".to_string(),
+                        position,
+                    )
+                    .with_help(
+                        "only variables and their elements, e.g. `arr[0]`, can be assigned to"
+                            .to_string(),
+                    ))
+                }
+            }
+        }
     }
 
     fn function_statement(&mut self) -> Result<FunctionStatement, Error> {
+        let doc = self.docs.remove(&self.current);
         self.advance();
         let identifier = self.eat(TokenType::Identifier)?;
+        let paramiters = self.paramiters()?;
+        let block = self.function_block()?;
+
+        let mut function_statement = FunctionStatement::new(identifier, paramiters, Some(block));
+        function_statement.doc = doc;
+        Ok(function_statement)
+    }
+
+    fn function_expression(&mut self) -> Result<FunctionStatement, Error> {
+        let keyword = self.next_token();
+        let identifier = Token::new(
+            TokenType::Identifier,
+            "lambda".to_string(),
+            None,
+            keyword.position,
+        );
+        let paramiters = self.paramiters()?;
+        let block = self.function_block()?;
+
+        Ok(FunctionStatement::new(identifier, paramiters, Some(block)))
+    }
+
+    fn paramiters(&mut self) -> Result<Vec<Parameter>, Error> {
         let mut paramiters = Vec::new();
         self.eat(TokenType::OpenParen)?;
         if !self.does_match(&[TokenType::CloseParen]) && !self.eof() {
             loop {
-                paramiters.push(Parameter::new(self.eat(TokenType::Identifier)?, false));
+                // A pack, e.g. `...rest`, takes the arguments that are left in an array.
+                if self.does_match(&[TokenType::DotDotDot]) {
+                    let dots = self.next_token();
+                    let identifier = self.eat(TokenType::Identifier)?;
+                    if self.does_match(&[TokenType::Equal]) {
+                        return Err(Error::new(
+                            ErrorType::ParsingError,
+                            format!(
+                                "The pack `...{}` can't have a default value",
+                                identifier.lexeme
+                            ),
+                            self.peek().position,
+                        )
+                        .with_note(
+                            "a pack is an empty array when there are no arguments left".to_string(),
+                        ));
+                    }
+                    if !self.does_match(&[TokenType::CloseParen]) {
+                        return Err(Error::new(
+                            ErrorType::ParsingError,
+                            format!(
+                                "The pack `...{}` must be the last parameter",
+                                identifier.lexeme
+                            ),
+                            dots.position.to(&identifier.position),
+                        ));
+                    }
+                    paramiters.push(Parameter::new(identifier, true));
+                    break;
+                }
+                let identifier = self.eat(TokenType::Identifier)?;
+                let mut paramiter = Parameter::new(identifier, false);
+                if self.does_match(&[TokenType::Equal]) {
+                    self.advance();
+                    paramiter = paramiter.with_default(self.expression()?);
+                } else if paramiters.iter().any(|p: &Parameter| p.default.is_some()) {
+                    return Err(Error::new(
+                        ErrorType::ParsingError,
+                        format!(
+                            "The parameter `{}` needs a default value, it follows one that has a default",
+                            paramiter.identifier.lexeme
+                        ),
+                        paramiter.identifier.position,
+                    ));
+                }
+                paramiters.push(paramiter);
                 if self.does_match(&[TokenType::Comma]) {
                     self.advance();
                 } else {
@@ -137,26 +336,79 @@ impl Parser {
             }
         }
         self.eat(TokenType::CloseParen)?;
-        let block = self.block_expression()?;
 
-        Ok(FunctionStatement::new(identifier, paramiters, Some(block)))
+        Ok(paramiters)
     }
 
-    fn if_expression(&mut self) -> Result<IfExpression, Error> {
+    fn function_block(&mut self) -> Result<BlockExpression, Error> {
+        // Loops outside of the function can't be controlled from inside of it.
+        let loop_depth = self.loop_depth;
+        self.loop_depth = 0;
+        let block = self.block_expression();
+        self.loop_depth = loop_depth;
+
+        block
+    }
+
+    fn while_statement(&mut self) -> Result<WhileStatement, Error> {
         self.advance();
         let condition = self.expression()?;
+        self.loop_depth += 1;
+        let block = self.block_expression();
+        self.loop_depth -= 1;
+
+        Ok(WhileStatement::new(condition, block?))
+    }
+
+    fn for_statement(&mut self) -> Result<ForStatement, Error> {
+        self.advance();
+        let identifier = self.eat(TokenType::Identifier)?;
+        self.eat(TokenType::In)?;
+        let iterable = self.expression()?;
+        self.loop_depth += 1;
+        let block = self.block_expression();
+        self.loop_depth -= 1;
+
+        Ok(ForStatement::new(identifier, iterable, block?))
+    }
+
+    fn loop_control_statement(&mut self) -> Result<Token, Error> {
+        let token = self.next_token();
+        if self.loop_depth == 0 {
+            Err(Error::new(
+                ErrorType::ParsingError,
+                format!("`{}` outside of a loop", token.ttype),
+                token.position.clone(),
+            )
+            .with_note(format!(
+                "`{}` can only be used inside of `while` and `for` loops",
+                token.ttype
+            )))
+        } else {
+            Ok(token)
+        }
+    }
+
+    fn if_expression(&mut self) -> Result<IfExpression, Error> {
+        let keyword = self.next_token();
+        let condition = self.expression()?;
         let if_block = self.block_expression()?;
+        let mut position = keyword.position.to(&if_block.position);
         let mut else_block = None;
         while self.does_match(&[TokenType::Else]) {
             self.advance();
             if self.does_match(&[TokenType::If]) {
-                else_block = Some(ElseBlock::If(self.if_expression()?));
+                let if_expression = self.if_expression()?;
+                position = position.to(&if_expression.position);
+                else_block = Some(ElseBlock::If(if_expression));
             } else {
-                else_block = Some(ElseBlock::Block(self.block_expression()?));
+                let block_expression = self.block_expression()?;
+                position = position.to(&block_expression.position);
+                else_block = Some(ElseBlock::Block(block_expression));
             }
         }
 
-        Ok(IfExpression::new(condition, if_block, else_block))
+        Ok(IfExpression::new(condition, if_block, else_block, position))
     }
 
     fn return_statement(&mut self) -> Result<Expression, Error> {
@@ -165,24 +417,62 @@ impl Parser {
     }
 
     fn block_expression(&mut self) -> Result<BlockExpression, Error> {
-        self.advance();
+        let open = self.eat(TokenType::OpenCurly)?;
         let mut statements = Vec::new();
         loop {
             if self.does_match(&[TokenType::CloseCurly]) || self.eof() {
                 break;
             }
-            statements.push(self.statemet()?);
+            if let Some(statement) = self.recoverable_statement() {
+                statements.push(statement);
+            }
         }
-        self.eat(TokenType::CloseCurly)?;
-        Ok(BlockExpression::new(statements))
+        let close = self.eat(TokenType::CloseCurly)?;
+        Ok(BlockExpression::new(
+            statements,
+            open.position.to(&close.position),
+        ))
     }
 
     fn expression(&mut self) -> Result<Expression, Error> {
         self.block()
     }
 
+    // A `{` starts a map instead of a block when it's followed by `"key":`, or by `:}` for an empty map.
+    fn is_map_literal(&self) -> bool {
+        self.does_match(&[TokenType::OpenCurly])
+            && match self.peek_nth(1).ttype {
+                TokenType::String => self.peek_nth(2).ttype == TokenType::Colon,
+                TokenType::Interpolation => {
+                    self.peek_nth(self.interpolation_end(1)).ttype == TokenType::Colon
+                }
+                TokenType::Colon => self.peek_nth(2).ttype == TokenType::CloseCurly,
+                _ => false,
+            }
+    }
+
+    // Returns the offset of the token after the interpolated string that starts at `offset`. The
+    // parts of the string that go on after a `}` start with one, the strings inside of the embedded
+    // expressions don't.
+    fn interpolation_end(&self, mut offset: usize) -> usize {
+        let mut depth = 0;
+        loop {
+            let token = self.peek_nth(offset);
+            offset += 1;
+            match token.ttype {
+                TokenType::Interpolation if !token.lexeme.starts_with('}') => depth += 1,
+                TokenType::String if token.lexeme.starts_with('}') => depth -= 1,
+                TokenType::EOF => return offset,
+                _ => {}
+            }
+            if depth == 0 {
+                return offset;
+            }
+        }
+    }
+
     fn block(&mut self) -> Result<Expression, Error> {
-        if self.peek().ttype == TokenType::OpenCurly {
+        if self.peek().ttype == TokenType::OpenCurly && !self.is_map_literal() {
             self.block_expression().map(Expression::Block)
         } else if self.peek().ttype == TokenType::If {
             self.if_expression().map(Expression::If)
@@ -228,7 +518,7 @@ impl Parser {
     }
 
     fn comparison(&mut self) -> Result<Expression, Error> {
-        let mut left = self.additive()?;
+        let mut left = self.range()?;
 
         while self.does_match(&[
             TokenType::Greater,
@@ -237,13 +527,27 @@ impl Parser {
             TokenType::LessEqual,
         ]) {
             let operator = self.next_token();
-            let right = self.additive()?;
+            let right = self.range()?;
             left = Expression::Binary(BinaryExpression::new(left, operator, right));
         }
 
         Ok(left)
     }
 
+    fn range(&mut self) -> Result<Expression, Error> {
+        let start = self.additive()?;
+
+        if self.does_match(&[TokenType::DotDot, TokenType::DotDotEqual]) {
+            let operator = self.next_token();
+            let end = self.additive()?;
+            return Ok(Expression::Range(RangeExpression::new(
+                start, operator, end,
+            )));
+        }
+
+        Ok(start)
+    }
+
     fn additive(&mut self) -> Result<Expression, Error> {
         let mut left = self.multiplicative()?;
 
@@ -271,30 +575,56 @@ impl Parser {
     fn unary(&mut self) -> Result<Expression, Error> {
         if self.does_match(&[TokenType::Minus, TokenType::Not]) {
             let operator = self.next_token();
-            let right = self.primary()?;
+            // The smallest integer can only be written negated, it's lexed with the negated value.
+            if operator.ttype == TokenType::Minus
+                && is_smallest_integer(&self.peek())
+                && !matches!(
+                    self.peek_nth(1).ttype,
+                    TokenType::OpenParen | TokenType::OpenBrack
+                )
+            {
+                let number = self.next_token();
+                return Ok(Expression::Literal(LiteralExpression::new(Token::new(
+                    TokenType::Number,
+                    format!("-{}", number.lexeme),
+                    number.literal,
+                    operator.position.to(&number.position),
+                ))));
+            }
+            let right = self.call()?;
             return Ok(Expression::Unary(UnaryExpression::new(operator, right)));
         }
-        self.primary()
+        self.call()
     }
 
-    fn primary(&mut self) -> Result<Expression, Error> {
-        if self.does_match(&[
-            TokenType::Number,
-            TokenType::String,
-            TokenType::Boolean,
-            TokenType::Nil,
-        ]) {
-            Ok(Expression::Literal(LiteralExpression::new(
-                self.next_token(),
-            )))
-        } else if self.does_match(&[TokenType::Identifier]) {
-            let identifier = self.next_token();
+    fn call(&mut self) -> Result<Expression, Error> {
+        let mut expression = self.primary()?;
+
+        loop {
             if self.does_match(&[TokenType::OpenParen]) {
                 self.advance();
                 let mut arguments = Vec::new();
+                let mut named = Vec::new();
                 if !self.does_match(&[TokenType::CloseParen]) {
                     loop {
-                        arguments.push(self.expression()?);
+                        if self.does_match(&[TokenType::Identifier])
+                            && self.peek_nth(1).ttype == TokenType::Colon
+                        {
+                            let name = self.next_token();
+                            self.advance();
+                            named.push((name, self.expression()?));
+                        } else {
+                            let argument = self.expression()?;
+                            if !named.is_empty() {
+                                return Err(Error::new(
+                                    ErrorType::ParsingError,
+                                    "Positional arguments must come before the named ones"
+                                        .to_string(),
+                                    argument.position(),
+                                ));
+                            }
+                            arguments.push(argument);
+                        }
                         if self.does_match(&[TokenType::Comma]) {
                             self.advance();
                         } else {
@@ -302,32 +632,128 @@ impl Parser {
                         }
                     }
                 }
-                self.eat(TokenType::CloseParen)?;
-                Ok(Expression::Call(CallExpression::new(identifier, arguments)))
+                let paren = self.eat(TokenType::CloseParen)?;
+                let position = expression.position().to(&paren.position);
+                expression =
+                    Expression::Call(CallExpression::new(expression, arguments, named, position));
+            } else if self.does_match(&[TokenType::OpenBrack]) {
+                self.advance();
+                let index = self.expression()?;
+                let bracket = self.eat(TokenType::CloseBrack)?;
+                let position = expression.position().to(&bracket.position);
+                expression = Expression::Index(IndexExpression::new(expression, index, position));
             } else {
-                Ok(Expression::Identifier(IdentifierExpression::new(
-                    identifier,
-                )))
+                break;
+            }
+        }
+
+        Ok(expression)
+    }
+
+    // The lexer splits the string at every embedded expression, e.g. `"a ${b} c"` is lexed into
+    // the `"a ${` interpolation, the tokens of `b`, and the `} c"` string.
+    fn interpolation_expression(&mut self) -> Result<InterpolationExpression, Error> {
+        let start = self.peek().position;
+        let mut parts = Vec::new();
+        loop {
+            let piece = self.next_token();
+            let is_last = piece.ttype == TokenType::String;
+            let end = piece.position.clone();
+            parts.push(Expression::Literal(LiteralExpression::new(piece)));
+            if is_last {
+                return Ok(InterpolationExpression::new(parts, start.to(&end)));
+            }
+            // The string that goes on after the `}` is the only token that starts with one.
+            if self.peek().lexeme.starts_with('}') {
+                return Err(Error::new(
+                    ErrorType::ParsingError,
+                    "Expected an expression inside `${}`".to_string(),
+                    end,
+                ));
+            }
+            parts.push(self.expression()?);
+            if !self.does_match(&[TokenType::Interpolation, TokenType::String]) {
+                let token = self.peek();
+                return Err(Error::new(
+                    ErrorType::ParsingError,
+                    format!(
+                        "Expected `}}` after the embedded expression, found `{}`",
+                        token.ttype
+                    ),
+                    token.position,
+                ));
+            }
+        }
+    }
+
+    fn primary(&mut self) -> Result<Expression, Error> {
+        if self.does_match(&[
+            TokenType::Number,
+            TokenType::String,
+            TokenType::Boolean,
+            TokenType::Nil,
+        ]) {
+            let token = self.next_token();
+            if is_smallest_integer(&token) {
+                return Err(too_large(&token.lexeme.replace('_', ""), token.position));
             }
+            Ok(Expression::Literal(LiteralExpression::new(token)))
+        } else if self.does_match(&[TokenType::Interpolation]) {
+            Ok(Expression::Interpolation(self.interpolation_expression()?))
+        } else if self.does_match(&[TokenType::Identifier]) {
+            Ok(Expression::Identifier(IdentifierExpression::new(
+                self.next_token(),
+            )))
+        } else if self.does_match(&[TokenType::Func]) {
+            Ok(Expression::Function(self.function_expression()?))
         } else if self.does_match(&[TokenType::OpenBrack]) {
-            self.advance();
-            let mut objects = Vec::new();
-            loop {
-                objects.push(self.next_token());
+            let open = self.next_token();
+            let mut elements = Vec::new();
+            // The elements can be empty, and the last element can be followed by a comma.
+            while !self.does_match(&[TokenType::CloseBrack]) {
+                elements.push(self.expression()?);
                 if self.does_match(&[TokenType::Comma]) {
                     self.advance();
-                    continue;
                 } else {
                     break;
                 }
             }
-            self.eat(TokenType::CloseBrack)?;
-            Ok(Expression::Array(ArrayExpression::new(objects)))
+            let close = self.eat(TokenType::CloseBrack)?;
+            Ok(Expression::Array(ArrayExpression::new(
+                elements,
+                open.position.to(&close.position),
+            )))
+        } else if self.is_map_literal() {
+            let curly = self.next_token();
+            let mut entries = Vec::new();
+            if self.does_match(&[TokenType::Colon]) {
+                self.advance();
+            } else {
+                while !self.does_match(&[TokenType::CloseCurly]) {
+                    let key = self.expression()?;
+                    self.eat(TokenType::Colon)?;
+                    let value = self.expression()?;
+                    entries.push((key, value));
+                    if self.does_match(&[TokenType::Comma]) {
+                        self.advance();
+                    } else {
+                        break;
+                    }
+                }
+            }
+            let close = self.eat(TokenType::CloseCurly)?;
+            Ok(Expression::Map(MapExpression::new(
+                entries,
+                curly.position.to(&close.position),
+            )))
         } else if self.does_match(&[TokenType::OpenParen]) {
-            self.advance();
+            let open = self.next_token();
             let child = self.expression()?;
-            self.eat(TokenType::CloseParen)?;
-            Ok(Expression::Group(GroupExpression::new(child)))
+            let close = self.eat(TokenType::CloseParen)?;
+            Ok(Expression::Group(GroupExpression::new(
+                child,
+                open.position.to(&close.position),
+            )))
         } else {
             let token = self.peek();
             Err(Error::new(
@@ -338,3 +764,8 @@ impl Parser {
         }
     }
 }
+
+// The lexer gives the digits of the smallest integer the negated value, e.g. `9223372036854775808`.
+fn is_smallest_integer(token: &Token) -> bool {
+    token.ttype == TokenType::Number && matches!(token.literal, Some(Object::Integer(i64::MIN, ..)))
+}
@@ -0,0 +1,301 @@
+use std::collections::HashSet;
+
+use crate::common::{
+    ast::{
+        BlockExpression, ElseBlock, Expression, FunctionStatement, IfExpression, Parameter,
+        Program, Statement,
+    },
+    error::{Error, ErrorType},
+    token::Token,
+};
+
+/// Checks that every variable is declared before it's being used, before the program runs.
+/// A function body is resolved at the first call of the function, against the names that are
+/// declared by then, so it can use the variables that are declared after the function but before
+/// the call. A function that's never called by it's name, e.g. a lambda or a function that's only
+/// called through a variable, is resolved at the end of the block it's defined in.
+pub struct Resolver<'a> {
+    scopes: Vec<HashSet<String>>,
+    // The functions of the top level are global, the ones in a function body are local to it.
+    functions: HashSet<String>,
+    // How many function bodies are being resolved.
+    function_depth: usize,
+    // The function bodies that aren't resolved yet, for every block that's being resolved.
+    deferred: Vec<Vec<Deferred<'a>>>,
+}
+
+// A function body that's resolved later.
+struct Deferred<'a> {
+    function_statement: &'a FunctionStatement,
+    // How many scopes are visible where the function is defined, the body can't see the others.
+    depth: usize,
+    // A named function is resolved at it's first call, a lambda at the end of it's block.
+    is_named: bool,
+}
+
+impl<'a> Resolver<'a> {
+    /// The globals are the names that are already defined, e.g. by the earlier lines of the REPL.
+    pub fn new(globals: Vec<String>) -> Self {
+        Self {
+            scopes: vec![globals.into_iter().collect()],
+            functions: HashSet::new(),
+            function_depth: 0,
+            deferred: Vec::new(),
+        }
+    }
+
+    pub fn resolve(&mut self, program: &'a Program) -> Result<(), Error> {
+        self.deferred.push(Vec::new());
+        for statement in program {
+            self.resolve_statement(statement)?;
+        }
+        self.resolve_deferred()
+    }
+
+    fn declare(&mut self, identifier: &Token) {
+        if let Some(scope) = self.scopes.last_mut() {
+            scope.insert(identifier.lexeme.clone());
+        }
+    }
+
+    fn is_declared(&self, identifier: &Token) -> bool {
+        self.functions.contains(&identifier.lexeme)
+            || self
+                .scopes
+                .iter()
+                .any(|scope| scope.contains(&identifier.lexeme))
+    }
+
+    fn resolve_identifier(&self, identifier: &Token) -> Result<(), Error> {
+        if self.is_declared(identifier) {
+            Ok(())
+        } else {
+            Err(Error::new(
+                ErrorType::ResolvingError,
+                format!("Variable `{}` doesn't exist.", identifier.lexeme),
+                identifier.position.clone(),
+            )
+            .with_help(format!(
+                "declare it before using it, e.g. `let {} = nil`",
+                identifier.lexeme
+            )))
+        }
+    }
+
+    fn resolve_deferred(&mut self) -> Result<(), Error> {
+        for deferred in self.deferred.pop().unwrap_or_default() {
+            self.resolve_deferred_function(deferred)?;
+        }
+        Ok(())
+    }
+
+    // Resolves the body of the called function, if it's not resolved yet.
+    fn resolve_callee(&mut self, identifier: &Token) -> Result<(), Error> {
+        for deferred in self.deferred.iter_mut().rev() {
+            let found = deferred.iter().position(|deferred| {
+                deferred.is_named
+                    && deferred.function_statement.identifier.lexeme == identifier.lexeme
+            });
+            if let Some(index) = found {
+                let deferred = deferred.remove(index);
+                return self.resolve_deferred_function(deferred);
+            }
+        }
+        Ok(())
+    }
+
+    fn resolve_deferred_function(&mut self, deferred: Deferred<'a>) -> Result<(), Error> {
+        // The scopes of the call are hidden, the body only sees the ones of the definition.
+        let hidden = self.scopes.split_off(deferred.depth);
+        let result = self.resolve_function(deferred.function_statement);
+        self.scopes.extend(hidden);
+        result
+    }
+
+    fn resolve_function(&mut self, function_statement: &'a FunctionStatement) -> Result<(), Error> {
+        if let Some(block_expression) = &function_statement.block {
+            self.scopes.push(HashSet::new());
+            self.function_depth += 1;
+            let result = self
+                .resolve_paramiters(&function_statement.paramiters)
+                .and_then(|()| self.resolve_block(block_expression));
+            self.function_depth -= 1;
+            self.scopes.pop();
+            result?;
+        }
+        Ok(())
+    }
+
+    fn resolve_paramiters(&mut self, paramiters: &'a [Parameter]) -> Result<(), Error> {
+        for parameter in paramiters {
+            // A default can use the parameters before it.
+            if let Some(default) = &parameter.default {
+                self.resolve_expression(default)?;
+            }
+            self.declare(&parameter.identifier);
+        }
+        Ok(())
+    }
+
+    fn defer(&mut self, function_statement: &'a FunctionStatement, is_named: bool) {
+        let depth = self.scopes.len();
+        if let Some(deferred) = self.deferred.last_mut() {
+            deferred.push(Deferred {
+                function_statement,
+                depth,
+                is_named,
+            });
+        }
+    }
+
+    fn resolve_block(&mut self, block_expression: &'a BlockExpression) -> Result<(), Error> {
+        self.scopes.push(HashSet::new());
+        self.deferred.push(Vec::new());
+        let mut result = block_expression
+            .statements
+            .iter()
+            .try_for_each(|statement| self.resolve_statement(statement));
+        if result.is_ok() {
+            result = self.resolve_deferred();
+        } else {
+            self.deferred.pop();
+        }
+        self.scopes.pop();
+        result
+    }
+
+    fn resolve_statement(&mut self, statement: &'a Statement) -> Result<(), Error> {
+        match statement {
+            Statement::Let(let_statement) => {
+                self.resolve_expression(&let_statement.expression)?;
+                self.declare(&let_statement.identifier);
+                Ok(())
+            }
+
+            Statement::Assignment(assignment_statement) => {
+                self.resolve_identifier(&assignment_statement.identifier)?;
+                for index in &assignment_statement.indices {
+                    self.resolve_expression(index)?;
+                }
+                self.resolve_expression(&assignment_statement.expression)
+            }
+
+            Statement::Function(function_statement) => {
+                if self.function_depth > 0 {
+                    self.declare(&function_statement.identifier);
+                } else {
+                    self.functions
+                        .insert(function_statement.identifier.lexeme.clone());
+                }
+                self.defer(function_statement, true);
+                Ok(())
+            }
+
+            Statement::While(while_statement) => {
+                self.resolve_expression(&while_statement.condition)?;
+                self.resolve_block(&while_statement.block)
+            }
+
+            Statement::For(for_statement) => {
+                self.resolve_expression(&for_statement.iterable)?;
+                self.scopes.push(HashSet::new());
+                self.declare(&for_statement.identifier);
+                let result = self.resolve_block(&for_statement.block);
+                self.scopes.pop();
+                result
+            }
+
+            Statement::Return(expression) | Statement::Expression(expression) => {
+                self.resolve_expression(expression)
+            }
+
+            Statement::Break(_) | Statement::Continue(_) => Ok(()),
+        }
+    }
+
+    fn resolve_if(&mut self, if_expression: &'a IfExpression) -> Result<(), Error> {
+        self.resolve_expression(&if_expression.condition)?;
+        self.resolve_block(&if_expression.if_block)?;
+        match &*if_expression.else_block {
+            Some(ElseBlock::Block(block_expression)) => self.resolve_block(block_expression),
+            Some(ElseBlock::If(if_expression)) => self.resolve_if(if_expression),
+            None => Ok(()),
+        }
+    }
+
+    fn resolve_expression(&mut self, expression: &'a Expression) -> Result<(), Error> {
+        match expression {
+            Expression::Block(block_expression) => self.resolve_block(block_expression),
+
+            Expression::Function(function_statement) => {
+                self.defer(function_statement, false);
+                Ok(())
+            }
+
+            Expression::If(if_expression) => self.resolve_if(if_expression),
+
+            Expression::Binary(binary_expression) => {
+                self.resolve_expression(&binary_expression.left)?;
+                self.resolve_expression(&binary_expression.right)
+            }
+
+            Expression::Unary(unary_expression) => self.resolve_expression(&unary_expression.right),
+
+            Expression::Group(group_expression) => self.resolve_expression(&group_expression.child),
+
+            Expression::Call(call_expression) => {
+                self.resolve_expression(&call_expression.callee)?;
+                for argument in &call_expression.arguments {
+                    self.resolve_expression(argument)?;
+                }
+                for (_, argument) in &call_expression.named {
+                    self.resolve_expression(argument)?;
+                }
+                match &*call_expression.callee {
+                    Expression::Identifier(identifier_expression) => {
+                        self.resolve_callee(&identifier_expression.identifier)
+                    }
+                    _ => Ok(()),
+                }
+            }
+
+            Expression::Identifier(identifier_expression) => {
+                self.resolve_identifier(&identifier_expression.identifier)
+            }
+
+            Expression::Literal(_) => Ok(()),
+
+            Expression::Interpolation(interpolation_expression) => {
+                for part in &interpolation_expression.parts {
+                    self.resolve_expression(part)?;
+                }
+                Ok(())
+            }
+
+            Expression::Array(array_expression) => {
+                for element in &array_expression.elements {
+                    self.resolve_expression(element)?;
+                }
+                Ok(())
+            }
+
+            Expression::Map(map_expression) => {
+                for (key, value) in &map_expression.entries {
+                    self.resolve_expression(key)?;
+                    self.resolve_expression(value)?;
+                }
+                Ok(())
+            }
+
+            Expression::Range(range_expression) => {
+                self.resolve_expression(&range_expression.start)?;
+                self.resolve_expression(&range_expression.end)
+            }
+
+            Expression::Index(index_expression) => {
+                self.resolve_expression(&index_expression.object)?;
+                self.resolve_expression(&index_expression.index)
+            }
+        }
+    }
+}
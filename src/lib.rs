@@ -1,3 +1,4 @@
 pub mod common;
 pub mod frontend;
+pub mod repl;
 pub mod runtime;
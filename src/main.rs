@@ -1,93 +1,277 @@
 use std::{
     env::args,
     fs::read_to_string,
-    io::{stdin, stdout, Write},
+    io::{stdin, Read},
+    process::exit,
 };
 
 use func::{
-    common::error::Error,
-    frontend::{lexer::Lexer, parser::Parser},
-    runtime::interpreter::Interpreter,
+    common::{
+        ast::Program,
+        error::{Diagnostics, Error, ErrorType},
+        position::Position,
+    },
+    frontend::{lexer::Lexer, parser::Parser, resolver::Resolver},
+    repl,
+    runtime::{compiler::Compiler, interpreter::Interpreter, vm::VirtualMachine},
 };
 
+// The status for invalid command-line arguments, the errors of the program have their own.
+const USAGE_ERROR: i32 = 64;
+
 fn main() {
-    run().unwrap_or_else(|err| err.report());
+    if let Err(err) = run() {
+        err.report();
+        exit(err.exit_code());
+    }
 }
 
-fn run() -> Result<(), Error> {
-    let args: Vec<String> = args().collect();
-
-    match args.len() {
-        1 => {
-            run_repl();
+fn run() -> Result<(), Diagnostics> {
+    let options = match Options::parse(args().skip(1)) {
+        Ok(options) => options,
+        Err(err) => {
+            print_usage(Some(&err));
+            exit(USAGE_ERROR);
         }
+    };
+
+    match options.command {
+        Command::Help => print_usage(None),
+
+        Command::Version => println!("func {}", env!("CARGO_PKG_VERSION")),
+
+        Command::Repl => exit(repl::run()),
 
-        2 => {
-            let source_path = &args[1];
-            if source_path == "-h" {
-                print_usage(None);
+        Command::Run(input) => {
+            let source = input.read()?;
+            if options.vm {
+                run_program_on_vm(input.source_path(), &source, &options.arguments)?;
             } else {
-                run_file(source_path)?;
+                run_program(input.source_path(), &source, &options.arguments)?;
+            }
+        }
+
+        Command::Check(input) => {
+            let program = parse(input.source_path(), &input.read()?)?;
+            let mut interpreter = Interpreter::new();
+            interpreter.declare_arguments(&[]);
+            Resolver::new(interpreter.names()).resolve(&program)?;
+        }
+
+        Command::Tokens(input) => {
+            for token in Lexer::new(input.source_path().to_string(), &input.read()?).lex()? {
+                println!(
+                    "{}:{}\t{:?}\t{}",
+                    token.position.row, token.position.column, token.ttype, token.lexeme
+                );
+            }
+        }
+
+        Command::Ast(input) => {
+            for statement in parse(input.source_path(), &input.read()?)? {
+                println!("{:#?}", statement);
             }
         }
-        _ => print_usage(Some("Invalid number of argument")),
     }
 
     Ok(())
 }
 
-fn repl_loop(interpreter: &mut Interpreter) -> Result<(), Error> {
-    let mut line = String::new();
-    loop {
-        print!(":> ");
-        stdout().flush().unwrap();
-        stdin().read_line(&mut line).unwrap();
+// Where the program is read from.
+enum Input {
+    File(String),
+    Stdin,
+    Inline(String),
+}
 
-        let mut lexer = Lexer::new("stdin".to_string(), line.trim());
-        let tokens = lexer.lex()?;
+impl Input {
+    fn source_path(&self) -> &str {
+        match self {
+            Self::File(source_path) => source_path,
+            Self::Stdin => "stdin",
+            Self::Inline(_) => "-e",
+        }
+    }
 
-        let mut parser = Parser::new(tokens);
-        let program = parser.parse()?;
-        interpreter.interpret(program)?;
+    fn read(&self) -> Result<String, Error> {
+        let result = match self {
+            Self::File(source_path) => read_to_string(source_path),
+            Self::Stdin => {
+                let mut source = String::new();
+                stdin().read_to_string(&mut source).map(|_| source)
+            }
+            Self::Inline(source) => Ok(source.clone()),
+        };
+        result.map_err(|err| {
+            Error::new(
+                ErrorType::IoError,
+                format!("Could not read `{}`, {}", self.source_path(), err),
+                Position::new(self.source_path().to_string(), 0),
+            )
+        })
+    }
+}
 
+enum Command {
+    Help,
+    Version,
+    Repl,
+    Run(Input),
+    Check(Input),
+    Tokens(Input),
+    Ast(Input),
+}
 
-        line.clear();
-    }
+struct Options {
+    command: Command,
+    vm: bool,
+    // The arguments after `--`, the script gets them in the `args` variable.
+    arguments: Vec<String>,
 }
 
-fn run_repl() {
-    println!("Press [Ctrl] + [c] exit.");
-    let mut interpreter = Interpreter::new();
-    loop {
-        if let Err(err) = repl_loop(&mut interpreter) {
-            err.report();
+impl Options {
+    fn parse(mut args: impl Iterator<Item = String>) -> Result<Self, String> {
+        let mut command = None;
+        let mut input = None;
+        let mut vm = false;
+        let mut arguments = Vec::new();
+
+        while let Some(arg) = args.next() {
+            let next_input = match arg.as_str() {
+                "-h" | "--help" | "help" => return Ok(Self::new(Command::Help)),
+                "-V" | "--version" => return Ok(Self::new(Command::Version)),
+                "--vm" => {
+                    vm = true;
+                    continue;
+                }
+                "--" => {
+                    arguments.extend(args.by_ref());
+                    continue;
+                }
+                "-e" => match args.next() {
+                    Some(code) => Input::Inline(code),
+                    None => return Err("`-e` expects the code to run".to_string()),
+                },
+                "-" => Input::Stdin,
+                _ if arg.starts_with('-') => return Err(format!("Unknown option `{}`", arg)),
+                "run" | "repl" | "check" | "tokens" | "ast"
+                    if command.is_none() && input.is_none() =>
+                {
+                    command = Some(arg);
+                    continue;
+                }
+                _ => Input::File(arg),
+            };
+            if input.is_some() {
+                return Err("Only one program can be given".to_string());
+            }
+            input = Some(next_input);
+        }
+
+        let command = match (command.as_deref(), input) {
+            (Some("repl"), Some(_)) => return Err("`repl` doesn't take a program".to_string()),
+            (Some("repl"), None) | (None, None) => Command::Repl,
+            (Some("check"), Some(input)) => Command::Check(input),
+            (Some("tokens"), Some(input)) => Command::Tokens(input),
+            (Some("ast"), Some(input)) => Command::Ast(input),
+            // Only `run` is left.
+            (_, Some(input)) => Command::Run(input),
+            (Some(command), None) => {
+                return Err(format!(
+                    "`{}` expects a file, `-` for stdin or `-e <code>`",
+                    command
+                ))
+            }
+        };
+        if vm && !matches!(command, Command::Run(_)) {
+            return Err("`--vm` only applies to running a program".to_string());
+        }
+        if !arguments.is_empty() && !matches!(command, Command::Run(_)) {
+            return Err("Only a program that runs takes arguments".to_string());
+        }
+
+        Ok(Self {
+            command,
+            vm,
+            arguments,
+        })
+    }
+
+    fn new(command: Command) -> Self {
+        Self {
+            command,
+            vm: false,
+            arguments: Vec::new(),
         }
     }
 }
 
-fn run_file(source_path: &str) -> Result<(), Error> {
-    let source = read_to_string(source_path).unwrap();
-
-    let mut lexer = Lexer::new(source_path.to_string(), &source);
+fn parse(source_path: &str, source: &str) -> Result<Program, Diagnostics> {
+    let mut lexer = Lexer::new(source_path.to_string(), source);
     let tokens = lexer.lex()?;
 
     let mut parser = Parser::new(tokens);
-    let program = parser.parse()?;
+    parser.parse()
+}
+
+fn run_program(source_path: &str, source: &str, arguments: &[String]) -> Result<(), Diagnostics> {
+    let program = parse(source_path, source)?;
 
     let mut interpreter = Interpreter::new();
+    interpreter.declare_arguments(arguments);
+
+    let mut resolver = Resolver::new(interpreter.names());
+    resolver.resolve(&program)?;
+
     interpreter.interpret(program)?;
 
     Ok(())
 }
 
+fn run_program_on_vm(
+    source_path: &str,
+    source: &str,
+    arguments: &[String],
+) -> Result<(), Diagnostics> {
+    let program = parse(source_path, source)?;
+
+    let mut vm = VirtualMachine::new();
+    vm.declare_arguments(arguments);
+
+    let mut resolver = Resolver::new(vm.names());
+    resolver.resolve(&program)?;
+
+    vm.run(Compiler::compile(&program))?;
+
+    Ok(())
+}
+
 fn print_usage(err: Option<&str>) {
     let usage = "
 Usage:
 
-func [source_file] | [options]
+func [options] [command] [-- arguments...]
+
+commands:
+run <program> :     runs the program, the default when only a program is given.
+repl :              starts the interactive session, the default without arguments.
+check <program> :   lexes, parses and resolves the program without running it.
+tokens <program> :  prints the tokens of the program.
+ast <program> :     prints the syntax tree of the program.
+
+A program is a source file, `-` to read it from stdin, or `-e <code>`.
+The arguments after `--` are passed to the program in the `args` array.
 
 options:
--h :   prints help.
+-e <code> :         uses the code as the program.
+--vm :              runs the program on the bytecode virtual machine.
+-h, --help :        prints help.
+-V, --version :     prints the version.
+
+exit status:
+0 on success, 1 for a runtime error, 2 for a lexing error, 3 for a parsing error,
+4 for a resolving error, 5 for an I/O error and 64 for invalid arguments.
+A program can choose it's own status with `exit(code)`.
 ";
     if let Some(err) = err {
         eprintln!("Error: {}.", err);
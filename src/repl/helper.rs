@@ -0,0 +1,231 @@
+use std::{borrow::Cow, collections::HashMap};
+
+use rustyline::{
+    completion::Completer,
+    highlight::Highlighter,
+    hint::{Hint, Hinter},
+    validate::Validator,
+    Context, Helper, Result,
+};
+
+use crate::{common::token::TokenType, frontend::lexer::Lexer, runtime::builtin::Builtin};
+
+const RESET: &str = "\x1b[0m";
+const KEYWORD: &str = "\x1b[35m";
+const LITERAL: &str = "\x1b[36m";
+const NUMBER: &str = "\x1b[33m";
+const STRING: &str = "\x1b[32m";
+const BUILTIN: &str = "\x1b[34m";
+const COMMENT: &str = "\x1b[90m";
+
+/// The parameters of a builtin that are left to write, shown at the right of the cursor.
+pub struct ParameterHint(String);
+
+impl Hint for ParameterHint {
+    fn display(&self) -> &str {
+        &self.0
+    }
+
+    // The hint only tells what to write, accepting it would fill the call with the parameter names.
+    fn completion(&self) -> Option<&str> {
+        None
+    }
+}
+
+/// Completes, colors and hints the lines of the REPL.
+pub struct ReplHelper {
+    keywords: HashMap<String, TokenType>,
+    // The parameters of every builtin, e.g. `push` has `["pushable", "value"]`.
+    builtins: HashMap<String, Vec<String>>,
+    // The variables and functions of the session, they change after every input.
+    names: Vec<String>,
+}
+
+impl ReplHelper {
+    pub fn new(names: Vec<String>) -> Self {
+        let builtins = Builtin::init()
+            .into_iter()
+            .map(|function| {
+                let paramiters = function
+                    .paramiters
+                    .iter()
+                    .map(|param| {
+                        if param.is_pack {
+                            format!("...{}", param.identifier.lexeme)
+                        } else {
+                            param.identifier.lexeme.clone()
+                        }
+                    })
+                    .collect();
+                (function.identifier.lexeme, paramiters)
+            })
+            .collect();
+
+        Self {
+            keywords: Lexer::keywords(),
+            builtins,
+            names,
+        }
+    }
+
+    pub fn set_names(&mut self, names: Vec<String>) {
+        self.names = names;
+    }
+
+    fn color(&self, ttype: &TokenType, lexeme: &str) -> Option<&'static str> {
+        match ttype {
+            TokenType::Boolean | TokenType::Nil => Some(LITERAL),
+            TokenType::Number => Some(NUMBER),
+            TokenType::DocComment => Some(COMMENT),
+            TokenType::String | TokenType::Interpolation => Some(STRING),
+            TokenType::Identifier if self.builtins.contains_key(lexeme) => Some(BUILTIN),
+            TokenType::Let
+            | TokenType::Func
+            | TokenType::If
+            | TokenType::Else
+            | TokenType::Return
+            | TokenType::While
+            | TokenType::For
+            | TokenType::In
+            | TokenType::Break
+            | TokenType::Continue => Some(KEYWORD),
+            _ => None,
+        }
+    }
+}
+
+fn is_identifier(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+// Colors the comments in the text between two tokens, it's only whitespace otherwise.
+fn push_between(highlighted: &mut String, text: &str) {
+    match text.find('/') {
+        Some(index) => {
+            highlighted.push_str(&text[..index]);
+            highlighted.push_str(COMMENT);
+            highlighted.push_str(&text[index..]);
+            highlighted.push_str(RESET);
+        }
+        None => highlighted.push_str(text),
+    }
+}
+
+impl Completer for ReplHelper {
+    type Candidate = String;
+
+    fn complete(&self, line: &str, pos: usize, _: &Context<'_>) -> Result<(usize, Vec<String>)> {
+        let start = line[..pos]
+            .rfind(|c| !is_identifier(c))
+            .map_or(0, |index| index + 1);
+        let prefix = &line[start..pos];
+        if prefix.is_empty() || prefix.starts_with(|c: char| c.is_ascii_digit()) {
+            return Ok((start, Vec::new()));
+        }
+
+        let mut candidates: Vec<String> = self
+            .keywords
+            .keys()
+            .chain(self.builtins.keys())
+            .chain(self.names.iter())
+            .filter(|name| name.starts_with(prefix))
+            .cloned()
+            .collect();
+        candidates.sort();
+        candidates.dedup();
+        Ok((start, candidates))
+    }
+}
+
+impl Hinter for ReplHelper {
+    type Hint = ParameterHint;
+
+    fn hint(&self, line: &str, pos: usize, _: &Context<'_>) -> Option<ParameterHint> {
+        // The hint is only shown where an argument starts, not in the middle of one.
+        if pos < line.len() || !line.trim_end().ends_with(['(', ',']) {
+            return None;
+        }
+
+        // Finds the paren of the call the cursor is in, and counts the arguments written so far.
+        let mut depth = 0;
+        let mut arguments = 0;
+        let mut paren = None;
+        for (index, c) in line.char_indices().rev() {
+            match c {
+                ')' | ']' | '}' => depth += 1,
+                '(' | '[' | '{' if depth > 0 => depth -= 1,
+                '(' => {
+                    paren = Some(index);
+                    break;
+                }
+                '[' | '{' => return None,
+                ',' if depth == 0 => arguments += 1,
+                _ => {}
+            }
+        }
+        let callee = line[..paren?].trim_end();
+        let start = callee
+            .rfind(|c| !is_identifier(c))
+            .map_or(0, |index| index + 1);
+        let paramiters = self.builtins.get(&callee[start..])?;
+
+        // A pack takes every argument that's left, so it stays in the hint.
+        let rest = match paramiters.last() {
+            Some(last) if last.starts_with("...") && arguments >= paramiters.len() => {
+                &paramiters[paramiters.len() - 1..]
+            }
+            _ => paramiters.get(arguments..)?,
+        };
+        if rest.is_empty() {
+            return None;
+        }
+        let separator = if line.ends_with(['(', ' ']) { "" } else { " " };
+        Some(ParameterHint(format!("{}{})", separator, rest.join(", "))))
+    }
+}
+
+impl Highlighter for ReplHelper {
+    fn highlight<'l>(&self, line: &'l str, _: usize) -> Cow<'l, str> {
+        // A line that doesn't lex yet, e.g. an open string, is shown as it is.
+        let tokens = match Lexer::new("stdin".to_string(), line).lex() {
+            Ok(tokens) => tokens,
+            Err(_) => return Cow::Borrowed(line),
+        };
+
+        let mut highlighted = String::with_capacity(line.len());
+        let mut end = 0;
+        for token in tokens {
+            let span = token.position.span;
+            if token.ttype == TokenType::EOF || span.start < end || span.end > line.len() {
+                continue;
+            }
+            push_between(&mut highlighted, &line[end..span.start]);
+            let text = &line[span.start..span.end];
+            match self.color(&token.ttype, text) {
+                Some(color) => {
+                    highlighted.push_str(color);
+                    highlighted.push_str(text);
+                    highlighted.push_str(RESET);
+                }
+                None => highlighted.push_str(text),
+            }
+            end = span.end;
+        }
+        push_between(&mut highlighted, &line[end..]);
+        Cow::Owned(highlighted)
+    }
+
+    fn highlight_hint<'h>(&self, hint: &'h str) -> Cow<'h, str> {
+        Cow::Owned(format!("{}{}{}", COMMENT, hint, RESET))
+    }
+
+    // Any change to the line can change the colors, e.g. a keyword that's being typed.
+    fn highlight_char(&self, _: &str, _: usize) -> bool {
+        true
+    }
+}
+
+// Inputs that span multiple lines are put together by the REPL itself.
+impl Validator for ReplHelper {}
+
+impl Helper for ReplHelper {}
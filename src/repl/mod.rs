@@ -0,0 +1,252 @@
+mod helper;
+
+use std::{env::var_os, fs::read_to_string, path::PathBuf, time::Instant};
+
+use rustyline::{error::ReadlineError, history::DefaultHistory, Editor};
+
+use crate::{
+    common::{
+        error::{Diagnostics, Error, ErrorType},
+        object::Object,
+        position::Position,
+    },
+    frontend::{lexer::Lexer, parser::Parser, resolver::Resolver},
+    runtime::interpreter::Interpreter,
+};
+
+use helper::ReplHelper;
+
+type ReplEditor = Editor<ReplHelper, DefaultHistory>;
+
+// The REPL keeps reading lines while a brace, bracket or paren is left open, or a string or a block
+// comment isn't closed.
+fn is_incomplete(source: &str) -> bool {
+    let mut depth = 0;
+    let mut comments = 0;
+    let mut in_string = false;
+    let mut chars = source.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '/' if !in_string && chars.peek() == Some(&'*') => {
+                chars.next();
+                comments += 1;
+            }
+            '*' if comments > 0 && chars.peek() == Some(&'/') => {
+                chars.next();
+                comments -= 1;
+            }
+            _ if comments > 0 => {}
+            '"' => in_string = !in_string,
+            // An escaped character, e.g. `\"`, never ends the string.
+            '\\' if in_string => {
+                chars.next();
+            }
+            _ if in_string => {}
+            '/' if chars.peek() == Some(&'/') => {
+                // Skip the comment until the end of the line.
+                for c in chars.by_ref() {
+                    if c == '\n' {
+                        break;
+                    }
+                }
+            }
+            '{' | '(' | '[' => depth += 1,
+            '}' | ')' | ']' => depth -= 1,
+            _ => {}
+        }
+    }
+    in_string || comments > 0 || depth > 0
+}
+
+// Reads the next input, which can be spread over multiple lines. Returns None once the user is done.
+fn read_input(editor: &mut ReplEditor) -> Option<String> {
+    let mut source = String::new();
+    loop {
+        let prompt = if source.is_empty() { ":> " } else { ".. " };
+        match editor.readline(prompt) {
+            Ok(line) => {
+                source.push_str(&line);
+                source.push('\n');
+                if !is_incomplete(&source) {
+                    return Some(source);
+                }
+            }
+            // [Ctrl] + [c] drops the current input.
+            Err(ReadlineError::Interrupted) => source.clear(),
+            Err(ReadlineError::Eof) => return None,
+            Err(err) => {
+                eprintln!("Error: {}.", err);
+                return None;
+            }
+        }
+    }
+}
+
+fn history_path() -> Option<PathBuf> {
+    var_os("HOME")
+        .or_else(|| var_os("USERPROFILE"))
+        .map(|home| PathBuf::from(home).join(".func_history"))
+}
+
+// Returns the value of the last statement if it's an expression, so the REPL can show it.
+fn run_source(
+    interpreter: &mut Interpreter,
+    source_path: &str,
+    source: &str,
+) -> Result<Option<Object>, Diagnostics> {
+    let mut lexer = Lexer::new(source_path.to_string(), source);
+    let tokens = lexer.lex()?;
+
+    let mut parser = Parser::new(tokens);
+    let program = parser.parse()?;
+
+    let mut resolver = Resolver::new(interpreter.names());
+    resolver.resolve(&program)?;
+
+    Ok(interpreter.evaluate(program)?)
+}
+
+const COMMANDS: &str = "
+:vars           lists the global variables.
+:funcs          lists the functions, builtins included.
+:load <file>    runs the file in the current session.
+:reset          forgets every variable and function.
+:ast <code>     prints the syntax tree of the code.
+:tokens <code>  prints the tokens of the code.
+:time <code>    runs the code and prints how long it took.
+:help           prints this message.
+";
+
+// The REPL commands start with a colon, they are handled before the input is lexed.
+fn run_command(interpreter: &mut Interpreter, input: &str) -> Result<(), Diagnostics> {
+    let (command, argument) = input.split_once(char::is_whitespace).unwrap_or((input, ""));
+    let argument = argument.trim();
+
+    match command {
+        ":vars" => {
+            for (name, value) in interpreter.variables() {
+                println!("{} = {}", name, value);
+            }
+        }
+
+        ":funcs" => {
+            for function in interpreter.functions() {
+                if function.is_builtin() {
+                    println!("{} (builtin)", function.signature());
+                } else {
+                    println!("{}", function.signature());
+                }
+                if let Some(doc) = &function.doc {
+                    for line in doc.lines() {
+                        println!("    {}", line);
+                    }
+                }
+            }
+        }
+
+        ":load" => {
+            let source = read_to_string(argument).map_err(|err| {
+                Error::new(
+                    ErrorType::IoError,
+                    format!("Could not read `{}`, {}", argument, err),
+                    Position::new(argument.to_string(), 0),
+                )
+            })?;
+            run_source(interpreter, argument, &source)?;
+        }
+
+        ":reset" => *interpreter = Interpreter::new(),
+
+        ":ast" => {
+            let tokens = Lexer::new("stdin".to_string(), argument).lex()?;
+            for statement in Parser::new(tokens).parse()? {
+                println!("{:#?}", statement);
+            }
+        }
+
+        ":tokens" => {
+            for token in Lexer::new("stdin".to_string(), argument).lex()? {
+                println!(
+                    "{}:{}\t{:?}\t{}",
+                    token.position.row, token.position.column, token.ttype, token.lexeme
+                );
+            }
+        }
+
+        ":time" => {
+            let start = Instant::now();
+            let result = run_source(interpreter, "stdin", argument);
+            let elapsed = start.elapsed();
+            if let Some(value) = result? {
+                println!("{}", value.repr());
+            }
+            println!("Took {:?}.", elapsed);
+        }
+
+        ":help" => println!("{}", COMMANDS.trim()),
+
+        _ => eprintln!("Unknown command `{}`, see `:help`.", command),
+    }
+
+    Ok(())
+}
+
+/// Runs the interactive session until the user exits it, returns the status to exit with.
+pub fn run() -> i32 {
+    let mut editor = match ReplEditor::new() {
+        Ok(editor) => editor,
+        Err(err) => {
+            let err = Error::new(
+                ErrorType::IoError,
+                format!("Could not start the REPL, {}", err),
+                Position::new("stdin".to_string(), 0),
+            );
+            err.report();
+            return err.exit_code();
+        }
+    };
+    let mut interpreter = Interpreter::new();
+    editor.set_helper(Some(ReplHelper::new(interpreter.names())));
+    let history = history_path();
+    if let Some(history) = &history {
+        // There is no history yet the first time the REPL runs.
+        let _ = editor.load_history(history);
+    }
+
+    println!("Press [Ctrl] + [d] to exit, type `:help` for the commands.");
+    let mut status = 0;
+    while let Some(source) = read_input(&mut editor) {
+        if source.trim().is_empty() {
+            continue;
+        }
+        let _ = editor.add_history_entry(source.trim_end());
+        let result = if source.starts_with(':') {
+            run_command(&mut interpreter, source.trim())
+        } else {
+            run_source(&mut interpreter, "stdin", &source).map(|value| {
+                if let Some(value) = value {
+                    println!("{}", value.repr());
+                }
+            })
+        };
+        match result {
+            // `exit` ends the session, the other errors don't.
+            Err(err) if err.is_exit() => {
+                status = err.exit_code();
+                break;
+            }
+            Err(err) => err.report(),
+            Ok(()) => {}
+        }
+        if let Some(helper) = editor.helper_mut() {
+            helper.set_names(interpreter.names());
+        }
+    }
+
+    if let Some(history) = &history {
+        if let Err(err) = editor.save_history(history) {
+            eprintln!("Error: failed to save the history, {}.", err);
+        }
+    }
+    status
+}
@@ -1,6 +1,9 @@
+use std::fmt::{self, Display, Formatter};
+
 use crate::common::{
     ast::{FunctionStatement, Parameter},
     error::{Error, ErrorType},
+    number::{BigInt, Decimal},
     object::{Meta, Object},
     position::Position,
     token::{Token, TokenType},
@@ -17,6 +20,16 @@ pub enum Builtin {
     Pop,
     Push,
     Format,
+    Keys,
+    Values,
+    Has,
+    Remove,
+    Exit,
+    Int,
+    Float,
+    BigInt,
+    Decimal,
+    Round,
 }
 
 impl Builtin {
@@ -126,6 +139,129 @@ impl Builtin {
                     false,
                 ),
             ],
+            Self::Keys => vec![Parameter::new(
+                Token::new(
+                    TokenType::Identifier,
+                    "map".to_string(),
+                    None,
+                    Position::new("builtin".to_string(), 0),
+                ),
+                false,
+            )],
+            Self::Values => vec![Parameter::new(
+                Token::new(
+                    TokenType::Identifier,
+                    "map".to_string(),
+                    None,
+                    Position::new("builtin".to_string(), 0),
+                ),
+                false,
+            )],
+            Self::Has => vec![
+                Parameter::new(
+                    Token::new(
+                        TokenType::Identifier,
+                        "map".to_string(),
+                        None,
+                        Position::new("builtin".to_string(), 0),
+                    ),
+                    false,
+                ),
+                Parameter::new(
+                    Token::new(
+                        TokenType::Identifier,
+                        "key".to_string(),
+                        None,
+                        Position::new("builtin".to_string(), 0),
+                    ),
+                    false,
+                ),
+            ],
+            Self::Remove => vec![
+                Parameter::new(
+                    Token::new(
+                        TokenType::Identifier,
+                        "map".to_string(),
+                        None,
+                        Position::new("builtin".to_string(), 0),
+                    ),
+                    false,
+                ),
+                Parameter::new(
+                    Token::new(
+                        TokenType::Identifier,
+                        "key".to_string(),
+                        None,
+                        Position::new("builtin".to_string(), 0),
+                    ),
+                    false,
+                ),
+            ],
+            Self::Exit => vec![Parameter::new(
+                Token::new(
+                    TokenType::Identifier,
+                    "code".to_string(),
+                    None,
+                    Position::new("builtin".to_string(), 0),
+                ),
+                false,
+            )],
+            Self::Int => vec![Parameter::new(
+                Token::new(
+                    TokenType::Identifier,
+                    "value".to_string(),
+                    None,
+                    Position::new("builtin".to_string(), 0),
+                ),
+                false,
+            )],
+            Self::Float => vec![Parameter::new(
+                Token::new(
+                    TokenType::Identifier,
+                    "value".to_string(),
+                    None,
+                    Position::new("builtin".to_string(), 0),
+                ),
+                false,
+            )],
+            Self::BigInt => vec![Parameter::new(
+                Token::new(
+                    TokenType::Identifier,
+                    "value".to_string(),
+                    None,
+                    Position::new("builtin".to_string(), 0),
+                ),
+                false,
+            )],
+            Self::Decimal => vec![Parameter::new(
+                Token::new(
+                    TokenType::Identifier,
+                    "value".to_string(),
+                    None,
+                    Position::new("builtin".to_string(), 0),
+                ),
+                false,
+            )],
+            Self::Round => vec![
+                Parameter::new(
+                    Token::new(
+                        TokenType::Identifier,
+                        "value".to_string(),
+                        None,
+                        Position::new("builtin".to_string(), 0),
+                    ),
+                    false,
+                ),
+                Parameter::new(
+                    Token::new(
+                        TokenType::Identifier,
+                        "places".to_string(),
+                        None,
+                        Position::new("builtin".to_string(), 0),
+                    ),
+                    false,
+                ),
+            ],
         }
     }
 
@@ -141,6 +277,16 @@ impl Builtin {
             Self::Readln,
             Self::Pop,
             Self::Push,
+            Self::Keys,
+            Self::Values,
+            Self::Has,
+            Self::Remove,
+            Self::Exit,
+            Self::Int,
+            Self::Float,
+            Self::BigInt,
+            Self::Decimal,
+            Self::Round,
         ]
         .iter()
         .map(|builtin| {
@@ -158,13 +304,43 @@ impl Builtin {
         .collect()
     }
 
+    // The values of a pack, written one after the other.
+    fn join(pack: &Object) -> String {
+        match pack {
+            Object::Array(values, ..) => values.iter().map(Object::to_string).collect(),
+            value => value.to_string(),
+        }
+    }
+
+    fn to_integer(integer: &BigInt, position: Position) -> Result<Object, Error> {
+        integer
+            .to_i64()
+            .map(|integer| Object::Integer(integer, Meta::default()))
+            .ok_or_else(|| {
+                Error::new(
+                    ErrorType::RuntimeError,
+                    format!("`{}` doesn't fit in an integer", integer),
+                    position,
+                )
+                .with_note(format!(
+                    "integers are between {} and {}",
+                    i64::MIN,
+                    i64::MAX
+                ))
+            })
+    }
+
     pub fn execute(&self, args: Vec<Object>, position: Position) -> Result<Object, Error> {
         match self {
             Builtin::Len => match &args[0] {
-                Object::String(string, ..) => {
-                    Ok(Object::Number(string.len() as f64, Meta::default()))
+                Object::String(string, ..) => Ok(Object::Integer(
+                    string.chars().count() as i64,
+                    Meta::default(),
+                )),
+                Object::Array(array, ..) => {
+                    Ok(Object::Integer(array.len() as i64, Meta::default()))
                 }
-                Object::Array(array, ..) => Ok(Object::Number(array.len() as f64, Meta::default())),
+                Object::Map(map, ..) => Ok(Object::Integer(map.len() as i64, Meta::default())),
                 _ => Err(Error::new(
                     ErrorType::RuntimeError,
                     format!("argument to `len` not supported, got {}", args[0]),
@@ -300,26 +476,26 @@ impl Builtin {
                 Ok(Object::String(result.to_string(), Meta::default()))
             }
             Builtin::Write => {
-                print!("{}", args[0]);
+                print!("{}", Self::join(&args[0]));
                 Ok(Object::Nil(Meta::default()))
             }
             Builtin::WriteLn => {
-                println!("{}", args[0]);
+                println!("{}", Self::join(&args[0]));
                 Ok(Object::Nil(Meta::default()))
             }
             Builtin::Readln => rustyline::DefaultEditor::new()
-                .map_err(|_| {
+                .map_err(|err| {
                     Error::new(
-                        ErrorType::RuntimeError,
-                        "failed to initialize readline".to_string(),
+                        ErrorType::IoError,
+                        format!("failed to initialize readline, {}", err),
                         position.clone(),
                     )
                 })?
                 .readline(&args[0].to_string())
-                .map_err(|_| {
+                .map_err(|err| {
                     Error::new(
-                        ErrorType::RuntimeError,
-                        "failed to read line".to_string(),
+                        ErrorType::IoError,
+                        format!("failed to read line, {}", err),
                         position,
                     )
                 })
@@ -350,22 +526,246 @@ impl Builtin {
                     position,
                 )),
             },
+            Builtin::Keys => match &args[0] {
+                Object::Map(map, ..) => Ok(Object::Array(
+                    map.keys()
+                        .map(|key| Object::String(key.clone(), Meta::default()))
+                        .collect(),
+                    Meta::default(),
+                )),
+                _ => Err(Error::new(
+                    ErrorType::RuntimeError,
+                    format!("argument to `keys` not supported, got {}", args[0]),
+                    position,
+                )),
+            },
+            Builtin::Values => match &args[0] {
+                Object::Map(map, ..) => Ok(Object::Array(
+                    map.values().cloned().collect(),
+                    Meta::default(),
+                )),
+                _ => Err(Error::new(
+                    ErrorType::RuntimeError,
+                    format!("argument to `values` not supported, got {}", args[0]),
+                    position,
+                )),
+            },
+            Builtin::Has => match &args[0] {
+                Object::Map(map, ..) => Ok(Object::Boolean(
+                    map.contains_key(&Object::key(&args[1], &position)?),
+                    Meta::default(),
+                )),
+                _ => Err(Error::new(
+                    ErrorType::RuntimeError,
+                    format!("argument to `has` not supported, got {}", args[0]),
+                    position,
+                )),
+            },
+            Builtin::Remove => match &args[0] {
+                Object::Map(map, ..) => {
+                    let mut map = map.clone();
+                    map.remove(&Object::key(&args[1], &position)?);
+                    Ok(Object::Map(map, Meta::default()))
+                }
+                _ => Err(Error::new(
+                    ErrorType::RuntimeError,
+                    format!("argument to `remove` not supported, got {}", args[0]),
+                    position,
+                )),
+            },
+            Builtin::Exit => match &args[0] {
+                Object::Integer(code, ..) if i32::try_from(*code).is_ok() => Err(Error::new(
+                    ErrorType::Exit(*code as i32),
+                    format!("exited with status {}", code),
+                    position,
+                )),
+                _ => Err(Error::new(
+                    ErrorType::RuntimeError,
+                    format!("argument to `exit` must be an integer, got {}", args[0]),
+                    position,
+                )),
+            },
+            Builtin::Int => match &args[0] {
+                Object::Integer(..) => Ok(args[0].clone()),
+                // The float is truncated toward zero, `int(-2.7)` is `-2`.
+                Object::Number(number, ..)
+                    if number.is_finite()
+                        && number.trunc() >= i64::MIN as f64
+                        && number.trunc() < i64::MAX as f64 =>
+                {
+                    Ok(Object::Integer(*number as i64, Meta::default()))
+                }
+                Object::BigInt(integer, ..) => Self::to_integer(integer, position),
+                Object::Decimal(decimal, ..) => Self::to_integer(&decimal.trunc(), position),
+                Object::Boolean(boolean, ..) => {
+                    Ok(Object::Integer(*boolean as i64, Meta::default()))
+                }
+                Object::String(string, ..) => string
+                    .trim()
+                    .parse()
+                    .map(|integer| Object::Integer(integer, Meta::default()))
+                    .map_err(|_| {
+                        Error::new(
+                            ErrorType::RuntimeError,
+                            format!("could not convert {:?} to an integer", string),
+                            position,
+                        )
+                    }),
+                _ => Err(Error::new(
+                    ErrorType::RuntimeError,
+                    format!("argument to `int` not supported, got {}", args[0]),
+                    position,
+                )),
+            },
+            Builtin::Float => match &args[0] {
+                Object::Integer(integer, ..) => {
+                    Ok(Object::Number(*integer as f64, Meta::default()))
+                }
+                Object::Number(..) => Ok(args[0].clone()),
+                Object::BigInt(integer, ..) => {
+                    Ok(Object::Number(integer.to_f64(), Meta::default()))
+                }
+                Object::Decimal(decimal, ..) => {
+                    Ok(Object::Number(decimal.to_f64(), Meta::default()))
+                }
+                Object::Boolean(boolean, ..) => {
+                    Ok(Object::Number(*boolean as i64 as f64, Meta::default()))
+                }
+                Object::String(string, ..) => string
+                    .trim()
+                    .parse()
+                    .map(|float| Object::Number(float, Meta::default()))
+                    .map_err(|_| {
+                        Error::new(
+                            ErrorType::RuntimeError,
+                            format!("could not convert {:?} to a float", string),
+                            position,
+                        )
+                    }),
+                _ => Err(Error::new(
+                    ErrorType::RuntimeError,
+                    format!("argument to `float` not supported, got {}", args[0]),
+                    position,
+                )),
+            },
+            Builtin::BigInt => match &args[0] {
+                Object::Integer(integer, ..) => {
+                    Ok(Object::BigInt(BigInt::from(*integer), Meta::default()))
+                }
+                Object::BigInt(..) => Ok(args[0].clone()),
+                Object::Decimal(decimal, ..) => {
+                    Ok(Object::BigInt(decimal.trunc(), Meta::default()))
+                }
+                // A float prints all of it's whole digits, so it's parsed back without the fraction.
+                Object::Number(number, ..) if number.is_finite() => Ok(Object::BigInt(
+                    BigInt::parse(&number.trunc().to_string()).unwrap_or_else(BigInt::zero),
+                    Meta::default(),
+                )),
+                Object::String(string, ..) => BigInt::parse(string.trim())
+                    .map(|integer| Object::BigInt(integer, Meta::default()))
+                    .ok_or_else(|| {
+                        Error::new(
+                            ErrorType::RuntimeError,
+                            format!("could not convert {:?} to a big integer", string),
+                            position,
+                        )
+                    }),
+                _ => Err(Error::new(
+                    ErrorType::RuntimeError,
+                    format!("argument to `bigint` not supported, got {}", args[0]),
+                    position,
+                )),
+            },
+            Builtin::Decimal => match &args[0] {
+                Object::Integer(integer, ..) => Ok(Object::Decimal(
+                    Decimal::from(BigInt::from(*integer)),
+                    Meta::default(),
+                )),
+                Object::BigInt(integer, ..) => Ok(Object::Decimal(
+                    Decimal::from(integer.clone()),
+                    Meta::default(),
+                )),
+                Object::Decimal(..) => Ok(args[0].clone()),
+                // The shortest digits that read back as the float, so `decimal(0.1)` is `0.1`.
+                Object::Number(number, ..) if number.is_finite() => Ok(Object::Decimal(
+                    Decimal::parse(&number.to_string())
+                        .unwrap_or_else(|| Decimal::from(BigInt::zero())),
+                    Meta::default(),
+                )),
+                Object::String(string, ..) => Decimal::parse(string.trim())
+                    .map(|decimal| Object::Decimal(decimal, Meta::default()))
+                    .ok_or_else(|| {
+                        Error::new(
+                            ErrorType::RuntimeError,
+                            format!("could not convert {:?} to a decimal", string),
+                            position,
+                        )
+                    }),
+                _ => Err(Error::new(
+                    ErrorType::RuntimeError,
+                    format!("argument to `decimal` not supported, got {}", args[0]),
+                    position,
+                )),
+            },
+            Builtin::Round => {
+                let places = match &args[1] {
+                    Object::Integer(places, ..) if u32::try_from(*places).is_ok() => *places as u32,
+                    _ => {
+                        return Err(Error::new(
+                            ErrorType::RuntimeError,
+                            format!(
+                                "the places of `round` must be a non-negative integer, got {}",
+                                args[1]
+                            ),
+                            position,
+                        ))
+                    }
+                };
+                match &args[0] {
+                    Object::Integer(..) | Object::BigInt(..) => Ok(args[0].clone()),
+                    Object::Decimal(decimal, ..) => {
+                        Ok(Object::Decimal(decimal.round(places), Meta::default()))
+                    }
+                    Object::Number(number, ..) => {
+                        let factor = 10f64.powi(places.min(308) as i32);
+                        Ok(Object::Number(
+                            (number * factor).round() / factor,
+                            Meta::default(),
+                        ))
+                    }
+                    _ => Err(Error::new(
+                        ErrorType::RuntimeError,
+                        format!("argument to `round` not supported, got {}", args[0]),
+                        position,
+                    )),
+                }
+            }
         }
     }
 }
 
-impl ToString for Builtin {
-    fn to_string(&self) -> String {
+impl Display for Builtin {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
         match self {
-            Self::Len => "len".to_string(),
-            Self::First => "first".to_string(),
-            Self::Last => "last".to_string(),
-            Self::Write => "write".to_string(),
-            Self::WriteLn => "writeln".to_string(),
-            Self::Readln => "readln".to_string(),
-            Self::Pop => "pop".to_string(),
-            Self::Push => "push".to_string(),
-            Self::Format => "format".to_string(),
+            Self::Len => write!(f, "len"),
+            Self::First => write!(f, "first"),
+            Self::Last => write!(f, "last"),
+            Self::Write => write!(f, "write"),
+            Self::WriteLn => write!(f, "writeln"),
+            Self::Readln => write!(f, "readln"),
+            Self::Pop => write!(f, "pop"),
+            Self::Push => write!(f, "push"),
+            Self::Format => write!(f, "format"),
+            Self::Keys => write!(f, "keys"),
+            Self::Values => write!(f, "values"),
+            Self::Has => write!(f, "has"),
+            Self::Remove => write!(f, "remove"),
+            Self::Exit => write!(f, "exit"),
+            Self::Int => write!(f, "int"),
+            Self::Float => write!(f, "float"),
+            Self::BigInt => write!(f, "bigint"),
+            Self::Decimal => write!(f, "decimal"),
+            Self::Round => write!(f, "round"),
         }
     }
 }
@@ -384,6 +784,16 @@ impl TryFrom<Token> for Builtin {
             "pop" => Ok(Self::Pop),
             "push" => Ok(Self::Push),
             "format" => Ok(Self::Format),
+            "keys" => Ok(Self::Keys),
+            "values" => Ok(Self::Values),
+            "has" => Ok(Self::Has),
+            "remove" => Ok(Self::Remove),
+            "exit" => Ok(Self::Exit),
+            "int" => Ok(Self::Int),
+            "float" => Ok(Self::Float),
+            "bigint" => Ok(Self::BigInt),
+            "decimal" => Ok(Self::Decimal),
+            "round" => Ok(Self::Round),
             _ => Err(Error::new(
                 ErrorType::RuntimeError,
                 format!("unknown builtin function: {}", value.lexeme),
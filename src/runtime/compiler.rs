@@ -0,0 +1,554 @@
+use std::rc::Rc;
+
+use crate::common::{
+    ast::{
+        AssignmentStatement, BlockExpression, CallExpression, ElseBlock, Expression, ForStatement,
+        FunctionStatement, IfExpression, IndexExpression, Parameter, Program, Statement,
+        WhileStatement,
+    },
+    object::Object,
+    position::Position,
+    token::{Token, TokenType},
+};
+
+/// An instruction of the virtual machine. The operands are indices into the pools of the chunk, or
+/// absolute jump targets. Every statement leaves exactly one value on the stack.
+#[derive(Debug, Clone)]
+pub enum Instruction {
+    /// Pushes a constant from the constant pool.
+    Constant(usize),
+    Nil,
+    Pop,
+    /// Pushes a variable, or the function with that name if there is no such variable.
+    GetVariable(usize),
+    /// Like `GetVariable`, but reports a missing function instead of a missing variable.
+    GetCallee(usize),
+    /// Declares the variable in the current scope, the value is left on the stack.
+    DefineVariable(usize),
+    /// Assigns to an existing variable, the value is left on the stack.
+    SetVariable(usize),
+    /// Assigns to an element of a variable, the indices are on the stack below the value.
+    SetIndex(usize, Vec<Position>),
+    /// Defines a global named function that closes over the current scope, and pushes nil.
+    DefineFunction(usize),
+    /// Pushes a lambda that closes over the current scope.
+    Closure(usize),
+    Binary(TokenType),
+    Unary(TokenType),
+    Range(TokenType),
+    Array(usize),
+    /// Joins the values on the stack into a string.
+    Interpolate(usize),
+    /// Builds a map out of the keys and values on the stack, the positions of the keys are kept for
+    /// error reporting.
+    Map(Vec<Position>),
+    Index,
+    Slice(bool),
+    /// Checks that the callee below the arguments is a function, before the arguments are evaluated.
+    /// The count is of the positional arguments, the named ones follow them.
+    CheckCall(usize, Vec<Token>),
+    Call(usize, Vec<Token>),
+    Return,
+    Jump(usize),
+    /// Pops the condition and jumps if it's false.
+    JumpIfFalse(usize),
+    /// Jumps if the parameter got an argument, otherwise it's default value is computed.
+    JumpIfBound(usize, usize),
+    PushScope,
+    PopScope,
+    /// Pops an iterable and starts iterating over it.
+    Iterate,
+    /// Pops the bounds of a range and starts counting through it.
+    IterateRange(TokenType),
+    /// Pushes the next item of the current iteration, or jumps once there are no items left.
+    ForNext(usize),
+    PopIterator,
+    /// Remembers the height of the stack and the current scope, so loops and returns can get back to them.
+    Mark,
+    Unmark,
+    /// Drops the given number of inner marks and goes back to the state of the innermost one left.
+    Unwind(usize),
+    /// Moves the top of the stack aside while the stack is being unwound.
+    StoreReturn,
+    LoadReturn,
+}
+
+/// A compiled piece of code with the pools it's instructions refer to.
+#[derive(Debug, Default)]
+pub struct Chunk {
+    pub code: Vec<Instruction>,
+    pub positions: Vec<Position>,
+    pub constants: Vec<Object>,
+    pub identifiers: Vec<Token>,
+    pub prototypes: Vec<Rc<Prototype>>,
+}
+
+/// A compiled function, it becomes a function value once it's closed over a scope.
+#[derive(Debug)]
+pub struct Prototype {
+    pub identifier: Token,
+    pub paramiters: Vec<Parameter>,
+    pub chunk: Rc<Chunk>,
+    pub doc: Option<String>,
+}
+
+struct Loop {
+    start: usize,
+    breaks: Vec<usize>,
+    marks: usize,
+}
+
+// Outside of functions a `return` stops the outermost block, `if` or loop it's in,
+// and that construct evaluates to the returned value.
+struct Boundary {
+    returns: Vec<usize>,
+    marks: usize,
+}
+
+pub struct Compiler {
+    chunk: Chunk,
+    position: Position,
+    in_function: bool,
+    marks: usize,
+    loops: Vec<Loop>,
+    boundary: Option<Boundary>,
+}
+
+impl Compiler {
+    fn new(in_function: bool, position: Position) -> Self {
+        Self {
+            chunk: Chunk::default(),
+            position,
+            in_function,
+            marks: 0,
+            loops: Vec::new(),
+            boundary: None,
+        }
+    }
+
+    /// Lowers the program into a chunk that can be run by the virtual machine.
+    pub fn compile(program: &Program) -> Chunk {
+        let mut compiler = Self::new(false, Position::new(String::new(), 0));
+        for statement in program {
+            if let Statement::Return(_) = statement {
+                // A `return` outside of any block doesn't do anything.
+                compiler.emit(Instruction::Nil);
+            } else {
+                compiler.statement(statement);
+            }
+            compiler.emit(Instruction::Pop);
+        }
+        compiler.chunk
+    }
+
+    fn emit(&mut self, instruction: Instruction) -> usize {
+        self.chunk.code.push(instruction);
+        self.chunk.positions.push(self.position.clone());
+        self.chunk.code.len() - 1
+    }
+
+    fn emit_at(&mut self, instruction: Instruction, position: &Position) -> usize {
+        self.position = position.clone();
+        self.emit(instruction)
+    }
+
+    fn patch(&mut self, jump: usize) {
+        let target = self.chunk.code.len();
+        match &mut self.chunk.code[jump] {
+            Instruction::Jump(address)
+            | Instruction::JumpIfFalse(address)
+            | Instruction::JumpIfBound(_, address)
+            | Instruction::ForNext(address) => *address = target,
+            _ => {}
+        }
+    }
+
+    fn constant(&mut self, object: Object) -> usize {
+        self.chunk.constants.push(object);
+        self.chunk.constants.len() - 1
+    }
+
+    fn identifier(&mut self, identifier: &Token) -> usize {
+        self.position = identifier.position.clone();
+        self.chunk.identifiers.push(identifier.clone());
+        self.chunk.identifiers.len() - 1
+    }
+
+    fn prototype(&mut self, function_statement: &FunctionStatement) -> usize {
+        let mut compiler = Self::new(true, function_statement.identifier.position.clone());
+        // The parameters without an argument are declared first, in the scope of the call.
+        for paramiter in &function_statement.paramiters {
+            if let Some(default) = &paramiter.default {
+                let identifier = compiler.identifier(&paramiter.identifier);
+                let bound = compiler.emit(Instruction::JumpIfBound(identifier, 0));
+                compiler.expression(default);
+                compiler.emit(Instruction::DefineVariable(identifier));
+                compiler.emit(Instruction::Pop);
+                compiler.patch(bound);
+            }
+        }
+        if let Some(block_expression) = &function_statement.block {
+            compiler.block(block_expression);
+        } else {
+            compiler.emit(Instruction::Nil);
+        }
+        compiler.emit(Instruction::Return);
+
+        self.chunk.prototypes.push(Rc::new(Prototype {
+            identifier: function_statement.identifier.clone(),
+            paramiters: function_statement.paramiters.clone(),
+            chunk: Rc::new(compiler.chunk),
+            doc: function_statement.doc.clone(),
+        }));
+        self.chunk.prototypes.len() - 1
+    }
+
+    fn mark(&mut self) {
+        self.emit(Instruction::Mark);
+        self.marks += 1;
+    }
+
+    fn unmark(&mut self) {
+        self.emit(Instruction::Unmark);
+        self.marks -= 1;
+    }
+
+    /// Compiles the construct inside of a return boundary, if it's the outermost one of the script.
+    fn bounded(&mut self, compile: impl FnOnce(&mut Self)) {
+        if self.in_function || self.boundary.is_some() {
+            compile(self);
+            return;
+        }
+
+        self.mark();
+        self.boundary = Some(Boundary {
+            returns: Vec::new(),
+            marks: self.marks,
+        });
+        compile(self);
+        let returns = self
+            .boundary
+            .take()
+            .map(|boundary| boundary.returns)
+            .unwrap_or_default();
+        let end = self.emit(Instruction::Jump(0));
+        for jump in returns {
+            self.patch(jump);
+        }
+        self.emit(Instruction::LoadReturn);
+        self.patch(end);
+        self.unmark();
+    }
+
+    fn block(&mut self, block_expression: &BlockExpression) {
+        self.emit(Instruction::PushScope);
+        if block_expression.statements.is_empty() {
+            self.emit(Instruction::Nil);
+        }
+        for (index, statement) in block_expression.statements.iter().enumerate() {
+            if index > 0 {
+                self.emit(Instruction::Pop);
+            }
+            self.statement(statement);
+        }
+        self.emit(Instruction::PopScope);
+    }
+
+    fn statement(&mut self, statement: &Statement) {
+        match statement {
+            Statement::Let(let_statement) => {
+                self.expression(&let_statement.expression);
+                let identifier = self.identifier(&let_statement.identifier);
+                self.emit(Instruction::DefineVariable(identifier));
+            }
+
+            Statement::Assignment(assignment_statement) => self.assignment(assignment_statement),
+
+            Statement::Function(function_statement) => {
+                let prototype = self.prototype(function_statement);
+                if self.in_function {
+                    // A function that's defined in another one is local to it, like a variable.
+                    let identifier = self.identifier(&function_statement.identifier);
+                    self.emit(Instruction::Closure(prototype));
+                    self.emit(Instruction::DefineVariable(identifier));
+                    self.emit(Instruction::Pop);
+                    self.emit(Instruction::Nil);
+                } else {
+                    self.emit(Instruction::DefineFunction(prototype));
+                }
+            }
+
+            Statement::While(while_statement) => {
+                self.bounded(|compiler| compiler.while_statement(while_statement))
+            }
+
+            Statement::For(for_statement) => {
+                self.bounded(|compiler| compiler.for_statement(for_statement))
+            }
+
+            Statement::Return(expression) => {
+                self.expression(expression);
+                if self.in_function {
+                    self.emit(Instruction::Return);
+                } else if let Some(boundary) = &self.boundary {
+                    let unwind = self.marks - boundary.marks;
+                    self.emit(Instruction::StoreReturn);
+                    self.emit(Instruction::Unwind(unwind));
+                    let jump = self.emit(Instruction::Jump(0));
+                    if let Some(boundary) = &mut self.boundary {
+                        boundary.returns.push(jump);
+                    }
+                }
+            }
+
+            Statement::Break(token) | Statement::Continue(token) => {
+                self.position = token.position.clone();
+                if let Some(innermost) = self.loops.last() {
+                    let unwind = self.marks - innermost.marks;
+                    let start = innermost.start;
+                    self.emit(Instruction::Unwind(unwind));
+                    if let Statement::Break(_) = statement {
+                        let jump = self.emit(Instruction::Jump(0));
+                        if let Some(innermost) = self.loops.last_mut() {
+                            innermost.breaks.push(jump);
+                        }
+                    } else {
+                        self.emit(Instruction::Jump(start));
+                    }
+                } else {
+                    self.emit(Instruction::Nil);
+                }
+            }
+
+            Statement::Expression(expression) => self.expression(expression),
+        }
+    }
+
+    fn assignment(&mut self, assignment_statement: &AssignmentStatement) {
+        let mut positions = Vec::new();
+        for index in &assignment_statement.indices {
+            self.expression(index);
+            positions.push(index.position());
+        }
+        self.expression(&assignment_statement.expression);
+        let identifier = self.identifier(&assignment_statement.identifier);
+        if positions.is_empty() {
+            self.emit(Instruction::SetVariable(identifier));
+        } else {
+            self.emit(Instruction::SetIndex(identifier, positions));
+        }
+    }
+
+    fn while_statement(&mut self, while_statement: &WhileStatement) {
+        self.mark();
+        let start = self.chunk.code.len();
+        self.expression(&while_statement.condition);
+        let exit = self.emit(Instruction::JumpIfFalse(0));
+        self.loop_body(start, |compiler| {
+            compiler.block(&while_statement.block);
+            compiler.emit(Instruction::Pop);
+        });
+        self.patch(exit);
+        self.unmark();
+        self.emit(Instruction::Nil);
+    }
+
+    fn for_statement(&mut self, for_statement: &ForStatement) {
+        // A range is counted as the loop goes, instead of being built up front.
+        if let Expression::Range(range_expression) = &for_statement.iterable {
+            self.expression(&range_expression.start);
+            self.expression(&range_expression.end);
+            self.emit_at(
+                Instruction::IterateRange(range_expression.operator.ttype.clone()),
+                &range_expression.operator.position,
+            );
+        } else {
+            self.expression(&for_statement.iterable);
+            self.emit_at(Instruction::Iterate, &for_statement.iterable.position());
+        }
+        self.mark();
+        let start = self.emit(Instruction::ForNext(0));
+        self.loop_body(start, |compiler| {
+            // Every iteration gets its own scope for the loop variable.
+            compiler.emit(Instruction::PushScope);
+            let identifier = compiler.identifier(&for_statement.identifier);
+            compiler.emit(Instruction::DefineVariable(identifier));
+            compiler.emit(Instruction::Pop);
+            compiler.block(&for_statement.block);
+            compiler.emit(Instruction::Pop);
+            compiler.emit(Instruction::PopScope);
+        });
+        self.patch(start);
+        self.unmark();
+        self.emit(Instruction::PopIterator);
+        self.emit(Instruction::Nil);
+    }
+
+    /// Compiles the body of a loop that starts at `start`, the breaks jump to the end of it.
+    fn loop_body(&mut self, start: usize, body: impl FnOnce(&mut Self)) {
+        self.loops.push(Loop {
+            start,
+            breaks: Vec::new(),
+            marks: self.marks,
+        });
+        body(self);
+        self.emit(Instruction::Jump(start));
+        if let Some(innermost) = self.loops.pop() {
+            for jump in innermost.breaks {
+                self.patch(jump);
+            }
+        }
+    }
+
+    fn if_expression(&mut self, if_expression: &IfExpression) {
+        self.expression(&if_expression.condition);
+        let otherwise = self.emit(Instruction::JumpIfFalse(0));
+        self.block(&if_expression.if_block);
+        let end = self.emit(Instruction::Jump(0));
+        self.patch(otherwise);
+        match &*if_expression.else_block {
+            Some(ElseBlock::Block(block_expression)) => self.block(block_expression),
+            Some(ElseBlock::If(if_expression)) => self.if_expression(if_expression),
+            None => {
+                self.emit(Instruction::Nil);
+            }
+        }
+        self.patch(end);
+    }
+
+    fn call(&mut self, call_expression: &CallExpression) {
+        match &*call_expression.callee {
+            // A name that isn't a variable is looked up among the functions.
+            Expression::Identifier(identifier_expression) => {
+                let identifier = self.identifier(&identifier_expression.identifier);
+                self.emit(Instruction::GetCallee(identifier));
+            }
+            callee => self.expression(callee),
+        }
+        let arguments = call_expression.arguments.len();
+        let names: Vec<Token> = call_expression
+            .named
+            .iter()
+            .map(|(name, _)| name.clone())
+            .collect();
+        self.emit_at(
+            Instruction::CheckCall(arguments, names.clone()),
+            &call_expression.position,
+        );
+        for argument in &call_expression.arguments {
+            self.expression(argument);
+        }
+        for (_, argument) in &call_expression.named {
+            self.expression(argument);
+        }
+        self.emit_at(
+            Instruction::Call(arguments, names),
+            &call_expression.position,
+        );
+    }
+
+    fn index(&mut self, index_expression: &IndexExpression) {
+        self.expression(&index_expression.object);
+        if let Expression::Range(range_expression) = &*index_expression.index {
+            self.expression(&range_expression.start);
+            self.expression(&range_expression.end);
+            self.emit_at(
+                Instruction::Slice(range_expression.is_inclusive()),
+                &index_expression.index.position(),
+            );
+        } else {
+            self.expression(&index_expression.index);
+            self.emit_at(Instruction::Index, &index_expression.index.position());
+        }
+    }
+
+    fn expression(&mut self, expression: &Expression) {
+        match expression {
+            Expression::Block(block_expression) => {
+                self.bounded(|compiler| compiler.block(block_expression))
+            }
+
+            Expression::Function(function_statement) => {
+                let prototype = self.prototype(function_statement);
+                self.emit(Instruction::Closure(prototype));
+            }
+
+            Expression::If(if_expression) => {
+                self.bounded(|compiler| compiler.if_expression(if_expression))
+            }
+
+            Expression::Binary(binary_expression) => {
+                self.expression(&binary_expression.left);
+                self.expression(&binary_expression.right);
+                self.emit_at(
+                    Instruction::Binary(binary_expression.operator.ttype.clone()),
+                    &binary_expression.operator.position,
+                );
+            }
+
+            Expression::Unary(unary_expression) => {
+                self.expression(&unary_expression.right);
+                self.emit_at(
+                    Instruction::Unary(unary_expression.operator.ttype.clone()),
+                    &unary_expression.operator.position,
+                );
+            }
+
+            Expression::Group(group_expression) => self.expression(&group_expression.child),
+
+            Expression::Call(call_expression) => self.call(call_expression),
+
+            Expression::Identifier(identifier_expression) => {
+                let identifier = self.identifier(&identifier_expression.identifier);
+                self.emit(Instruction::GetVariable(identifier));
+            }
+
+            Expression::Literal(literal_expression) => {
+                self.position = literal_expression.object.position.clone();
+                if let Some(object) = &literal_expression.object.literal {
+                    let constant = self.constant(object.clone());
+                    self.emit(Instruction::Constant(constant));
+                } else {
+                    self.emit(Instruction::Nil);
+                }
+            }
+
+            Expression::Interpolation(interpolation_expression) => {
+                for part in &interpolation_expression.parts {
+                    self.expression(part);
+                }
+                self.emit(Instruction::Interpolate(
+                    interpolation_expression.parts.len(),
+                ));
+            }
+
+            Expression::Array(array_expression) => {
+                for element in &array_expression.elements {
+                    self.expression(element);
+                }
+                self.emit(Instruction::Array(array_expression.elements.len()));
+            }
+
+            Expression::Map(map_expression) => {
+                let mut keys = Vec::new();
+                for (key, value) in &map_expression.entries {
+                    self.expression(key);
+                    self.expression(value);
+                    keys.push(key.position());
+                }
+                self.emit_at(Instruction::Map(keys), &map_expression.position);
+            }
+
+            Expression::Range(range_expression) => {
+                self.expression(&range_expression.start);
+                self.expression(&range_expression.end);
+                self.emit_at(
+                    Instruction::Range(range_expression.operator.ttype.clone()),
+                    &range_expression.operator.position,
+                );
+            }
+
+            Expression::Index(index_expression) => self.index(index_expression),
+        }
+    }
+}
@@ -1,45 +1,107 @@
-use std::collections::HashMap;
+use std::{any::Any, cell::RefCell, collections::HashMap, rc::Rc};
 
 use crate::common::{
-    ast::FunctionStatement,
     error::{Error, ErrorType},
-    object::Object,
+    object::{Function, Object},
     token::Token,
 };
 
-use super::builtin::Builtin;
+use super::{builtin::Builtin, compiler::Chunk};
 
-#[derive(Debug, Clone, Default)]
+/// A shared handle to a scope, blocks and closures keep their parent scope alive through it.
+pub type Environment = Rc<RefCell<VariableBindings>>;
+
+/// What the runtime captures in the functions it creates: the scope the function was defined in,
+/// and the compiled chunk if the virtual machine created it.
+pub struct Closure {
+    pub scope: Environment,
+    pub chunk: Option<Rc<Chunk>>,
+}
+
+impl Closure {
+    /// Captures the scope, and the chunk, for the `captured` field of a function.
+    pub fn capture(scope: Environment, chunk: Option<Rc<Chunk>>) -> Rc<dyn Any> {
+        Rc::new(Self { scope, chunk })
+    }
+
+    /// Returns the closure of the function, builtins don't have one.
+    pub fn of(function: &Function) -> Option<&Self> {
+        function.captured.as_ref()?.downcast_ref()
+    }
+}
+
+/// The variables of a single scope. Variables that aren't found here are looked up in the parent scope.
+#[derive(Debug, Default)]
 pub struct VariableBindings {
     bindings: HashMap<String, Object>,
+    parent: Option<Environment>,
 }
 
 impl VariableBindings {
-    pub fn declare(&mut self, identifier: Token, value: Object) {
-        self.bindings.insert(identifier.lexeme, value);
+    /// Creates a new scope inside of the parent scope.
+    pub fn new(parent: Environment) -> Environment {
+        Rc::new(RefCell::new(Self {
+            bindings: HashMap::new(),
+            parent: Some(parent),
+        }))
+    }
+
+    pub fn declare(&mut self, identifier: &Token, value: Object) {
+        self.bindings.insert(identifier.lexeme.clone(), value);
     }
 
-    pub fn get(&self, identifier: Token) -> Result<Object, Error> {
+    pub fn get(&self, identifier: &Token) -> Result<Object, Error> {
         if let Some(value) = self.bindings.get(&identifier.lexeme) {
             Ok(value.clone())
+        } else if let Some(parent) = &self.parent {
+            parent.borrow().get(identifier)
         } else {
             Err(Error::new(
                 ErrorType::RuntimeError,
                 format!("Variable `{}` doesn't exist.", identifier.lexeme),
-                identifier.position,
+                identifier.position.clone(),
             ))
         }
     }
 
-    pub fn assign(&mut self, identifier: Token, value: Object) -> Result<(), Error> {
-        if self.bindings.get(&identifier.lexeme).is_some() {
-            self.declare(identifier, value);
+    /// Whether the variable is declared in this very scope, the parent scopes aren't looked at.
+    pub fn contains(&self, identifier: &Token) -> bool {
+        self.bindings.contains_key(&identifier.lexeme)
+    }
+
+    /// Returns the scope this scope was created in.
+    pub fn parent(&self) -> Option<Environment> {
+        self.parent.clone()
+    }
+
+    /// Returns the names of the variables declared in this scope.
+    pub fn names(&self) -> Vec<String> {
+        self.bindings.keys().cloned().collect()
+    }
+
+    /// Returns the variables declared in this scope, sorted by their names.
+    pub fn entries(&self) -> Vec<(String, Object)> {
+        let mut entries: Vec<_> = self
+            .bindings
+            .iter()
+            .map(|(name, value)| (name.clone(), value.clone()))
+            .collect();
+        entries.sort_by(|(a, _), (b, _)| a.cmp(b));
+        entries
+    }
+
+    /// Assigns to the variable in the closest scope that declared it.
+    pub fn assign(&mut self, identifier: &Token, value: Object) -> Result<(), Error> {
+        if let Some(variable) = self.bindings.get_mut(&identifier.lexeme) {
+            *variable = value;
             Ok(())
+        } else if let Some(parent) = &self.parent {
+            parent.borrow_mut().assign(identifier, value)
         } else {
             Err(Error::new(
                 ErrorType::RuntimeError,
                 format!("Variable `{}` doesn't exist.", identifier.lexeme),
-                identifier.position,
+                identifier.position.clone(),
             ))
         }
     }
@@ -47,22 +109,34 @@ impl VariableBindings {
 
 #[derive(Debug, Clone)]
 pub struct FunctionBindings {
-    bindings: HashMap<String, FunctionStatement>,
+    bindings: HashMap<String, Rc<Function>>,
 }
 
 impl FunctionBindings {
-    pub fn put(&mut self, identifier: Token, value: FunctionStatement) {
-        self.bindings.insert(identifier.lexeme, value);
+    pub fn put(&mut self, identifier: &Token, value: Function) {
+        self.bindings
+            .insert(identifier.lexeme.clone(), Rc::new(value));
     }
 
-    pub fn get(&self, identifier: Token) -> Result<FunctionStatement, Error> {
+    pub fn names(&self) -> Vec<String> {
+        self.bindings.keys().cloned().collect()
+    }
+
+    /// Returns every function, sorted by their names.
+    pub fn functions(&self) -> Vec<Rc<Function>> {
+        let mut functions: Vec<_> = self.bindings.values().cloned().collect();
+        functions.sort_by(|a, b| a.identifier.lexeme.cmp(&b.identifier.lexeme));
+        functions
+    }
+
+    pub fn get(&self, identifier: &Token) -> Result<Rc<Function>, Error> {
         if let Some(value) = self.bindings.get(&identifier.lexeme) {
-            Ok(value.clone())
+            Ok(Rc::clone(value))
         } else {
             Err(Error::new(
                 ErrorType::RuntimeError,
                 format!("Function `{}` doesn't exist.", identifier.lexeme),
-                identifier.position,
+                identifier.position.clone(),
             ))
         }
     }
@@ -72,8 +146,8 @@ impl Default for FunctionBindings {
     fn default() -> Self {
         Self {
             bindings: Builtin::init()
-                .iter()
-                .map(|f| (f.identifier.lexeme.clone(), f.clone()))
+                .into_iter()
+                .map(|f| (f.identifier.lexeme.clone(), Rc::new(Function::new(f, None))))
                 .collect(),
         }
     }
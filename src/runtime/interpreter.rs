@@ -1,25 +1,32 @@
-use std::cmp::Ordering;
+use std::{collections::BTreeMap, rc::Rc};
 
 use crate::common::{
     ast::{
         AssignmentStatement, BinaryExpression, BlockExpression, CallExpression, ElseBlock,
-        Expression, FunctionStatement, GroupExpression, IdentifierExpression, IfExpression,
-        LetStatement, Program, Statement, UnaryExpression,
+        Expression, ForStatement, FunctionStatement, GroupExpression, IdentifierExpression,
+        IfExpression, IndexExpression, LetStatement, Program, RangeExpression, Statement,
+        UnaryExpression, WhileStatement,
     },
-    error::{Error, ErrorType},
-    object::{Meta, Object},
-    token::TokenType,
+    error::{Error, ErrorType, StackFrame},
+    object::{Function, Meta, Object},
+    position::Position,
+    token::{Token, TokenType},
 };
 
 use super::{
     builtin::Builtin,
-    environment::{FunctionBindings, VariableBindings},
+    environment::{Closure, Environment, FunctionBindings, VariableBindings},
+    operator,
 };
 
 #[derive(Default)]
 pub struct Interpreter {
-    variables: VariableBindings,
+    variables: Environment,
     functions: FunctionBindings,
+    // The user defined functions that are currently running, for the traceback of runtime errors.
+    call_stack: Vec<StackFrame>,
+    // Outside of the functions, a `return` gives the value of the outermost block, if or loop.
+    is_bounded: bool,
 }
 
 impl Interpreter {
@@ -27,11 +34,53 @@ impl Interpreter {
         Self::default()
     }
 
+    /// Returns the names of every global variable and function, builtins included.
+    pub fn names(&self) -> Vec<String> {
+        let mut names = self.variables.borrow().names();
+        names.extend(self.functions.names());
+        names
+    }
+
+    /// Declares the global `args` variable, the arguments that were passed to the script.
+    pub fn declare_arguments(&mut self, arguments: &[String]) {
+        let arguments = arguments
+            .iter()
+            .map(|argument| Object::String(argument.clone(), Meta::default()))
+            .collect();
+        self.variables.borrow_mut().declare(
+            &Token::new(
+                TokenType::Identifier,
+                "args".to_string(),
+                None,
+                Position::new("builtin".to_string(), 0),
+            ),
+            Object::Array(arguments, Meta::default()),
+        );
+    }
+
+    /// Returns the global variables, sorted by their names.
+    pub fn variables(&self) -> Vec<(String, Object)> {
+        self.variables.borrow().entries()
+    }
+
+    /// Returns every function, builtins included, sorted by their names.
+    pub fn functions(&self) -> Vec<Rc<Function>> {
+        self.functions.functions()
+    }
+
     pub fn interpret(&mut self, program: Program) -> Result<(), Error> {
+        self.evaluate(program).map(|_| ())
+    }
+
+    /// Runs the program and returns the value of it's last statement, if that's an expression.
+    pub fn evaluate(&mut self, program: Program) -> Result<Option<Object>, Error> {
+        let is_expression = matches!(program.last(), Some(Statement::Expression(_)));
+        let mut value = Object::Nil(Meta::default());
         for statement in program {
-            self.execute_statement(statement)?;
+            value = self.execute_statement(statement)?;
         }
-        Ok(())
+        value.clear_interrupt();
+        Ok(if is_expression { Some(value) } else { None })
     }
 
     fn execute_statement(&mut self, statement: Statement) -> Result<Object, Error> {
@@ -46,15 +95,114 @@ impl Interpreter {
                 self.define_function_statement(function_statement)
             }
 
+            Statement::While(while_statement) => {
+                self.bounded(|interpreter| interpreter.execute_while_statement(while_statement))
+            }
+
+            Statement::For(for_statement) => {
+                self.bounded(|interpreter| interpreter.execute_for_statement(for_statement))
+            }
+
+            Statement::Break(_) => {
+                let mut object = Object::Nil(Meta::default());
+                object.set_break();
+                Ok(object)
+            }
+
+            Statement::Continue(_) => {
+                let mut object = Object::Nil(Meta::default());
+                object.set_continue();
+                Ok(object)
+            }
+
             Statement::Expression(expression) => self.evaluate_expression(expression),
             _ => Ok(Object::Nil(Meta::default())),
         }
     }
 
+    fn execute_while_statement(
+        &mut self,
+        while_statement: WhileStatement,
+    ) -> Result<Object, Error> {
+        loop {
+            let condition = self.evaluate_expression(while_statement.condition.clone())?;
+            if condition.is_interrupt() {
+                return Ok(condition);
+            }
+            if !condition.is_true() {
+                break;
+            }
+            let value = self.evaluate_block_expression(while_statement.block.clone())?;
+            if value.is_return() {
+                return Ok(value);
+            }
+            if value.is_break() {
+                break;
+            }
+        }
+
+        Ok(Object::Nil(Meta::default()))
+    }
+
+    fn execute_for_statement(&mut self, for_statement: ForStatement) -> Result<Object, Error> {
+        let identifier = for_statement.identifier;
+        let position = for_statement.iterable.position();
+        // A range is counted as the loop goes, instead of being built up front.
+        let items = match for_statement.iterable {
+            Expression::Range(range_expression) => {
+                let start = self.evaluate_expression(*range_expression.start)?;
+                if start.is_interrupt() {
+                    return Ok(start);
+                }
+                let end = self.evaluate_expression(*range_expression.end)?;
+                if end.is_interrupt() {
+                    return Ok(end);
+                }
+                operator::range_items(
+                    start,
+                    end,
+                    &range_expression.operator.ttype,
+                    range_expression.operator.position,
+                )?
+            }
+            iterable => {
+                let iterable = self.evaluate_expression(iterable)?;
+                if iterable.is_interrupt() {
+                    return Ok(iterable);
+                }
+                operator::items(iterable, position)?
+            }
+        };
+
+        let mut return_value = Object::Nil(Meta::default());
+        for item in items {
+            // Every iteration gets its own scope for the loop variable.
+            let scope = VariableBindings::new(Rc::clone(&self.variables));
+            scope.borrow_mut().declare(&identifier, item);
+            let value = self.in_scope(scope, |interpreter| {
+                interpreter.evaluate_block_expression(for_statement.block.clone())
+            })?;
+            if value.is_return() {
+                return_value = value;
+                break;
+            }
+            if value.is_break() {
+                break;
+            }
+        }
+
+        Ok(return_value)
+    }
+
     fn execute_let_statement(&mut self, let_statement: LetStatement) -> Result<Object, Error> {
         let identifier = let_statement.identifier;
         let value = self.evaluate_expression(let_statement.expression)?;
-        self.variables.declare(identifier, value.clone());
+        if value.is_interrupt() {
+            return Ok(value);
+        }
+        self.variables
+            .borrow_mut()
+            .declare(&identifier, value.clone());
 
         Ok(value)
     }
@@ -64,9 +212,27 @@ impl Interpreter {
         assignment_statement: AssignmentStatement,
     ) -> Result<Object, Error> {
         let identifier = assignment_statement.identifier;
-        self.variables.get(identifier.clone())?;
+        let mut variable = self.variables.borrow().get(&identifier)?;
+        let mut indices = Vec::new();
+        for index in assignment_statement.indices {
+            let position = index.position();
+            let index = self.evaluate_expression(index)?;
+            if index.is_interrupt() {
+                return Ok(index);
+            }
+            indices.push((index, position));
+        }
         let value = self.evaluate_expression(assignment_statement.expression)?;
-        self.variables.assign(identifier, value.clone())?;
+        if value.is_interrupt() {
+            return Ok(value);
+        }
+        let element = value.clone();
+        if indices.is_empty() {
+            variable = element;
+        } else {
+            operator::assign_index(&mut variable, &indices, element)?;
+        }
+        self.variables.borrow_mut().assign(&identifier, variable)?;
 
         Ok(value)
     }
@@ -75,40 +241,86 @@ impl Interpreter {
         &mut self,
         function_statement: FunctionStatement,
     ) -> Result<Object, Error> {
-        self.functions
-            .put(function_statement.identifier.clone(), function_statement);
+        // The function sees the variables of the scope it's defined in, not the ones of it's caller.
+        let identifier = function_statement.identifier.clone();
+        let closure = Closure::capture(Rc::clone(&self.variables), None);
+        let function = Function::new(function_statement, Some(closure));
+        if self.call_stack.is_empty() {
+            self.functions.put(&identifier, function);
+        } else {
+            // A function that's defined in another one is local to it, like a variable.
+            self.variables.borrow_mut().declare(
+                &identifier,
+                Object::Function(Rc::new(function), Meta::default()),
+            );
+        }
         Ok(Object::Nil(Meta::default()))
     }
 
-    fn execute_function_statement(
+    // The arguments are the ones `Function::bind_arguments` returns, None takes the default value.
+    fn execute_function(
         &mut self,
-        arguments: Vec<Expression>,
-        function_statement: FunctionStatement,
+        function: &Function,
+        arguments: Vec<Option<Object>>,
+        position: Position,
     ) -> Result<Object, Error> {
-        let old_variables = self.variables.clone();
-        for (param, argument) in function_statement.paramiters.iter().zip(arguments.iter()) {
-            let value = self.evaluate_expression(argument.clone())?;
-            self.variables.declare(param.identifier.clone(), value);
-        }
-        let return_value = if let Some(block_expression) = function_statement.block {
-            self.evaluate_block_expression(block_expression)?
+        let block_expression = if let Some(block_expression) = &function.block {
+            block_expression.clone()
         } else {
             // If there is no block expression, that means the function is built-in.
-            Builtin::try_from(function_statement.identifier.clone())?.execute(
-                arguments
-                    .iter()
-                    .map(|expression| self.evaluate_expression(expression.clone()))
-                    .collect::<Result<Vec<_>, _>>()?,
-                function_statement.identifier.position,
-            )?
+            let arguments = arguments
+                .into_iter()
+                .map(|argument| argument.unwrap_or(Object::Nil(Meta::default())))
+                .collect();
+            return Builtin::try_from(function.identifier.clone())?.execute(arguments, position);
         };
 
-        self.variables = old_variables;
+        let parent = match Closure::of(function) {
+            Some(closure) => Rc::clone(&closure.scope),
+            None => Rc::clone(&self.variables),
+        };
+        let scope = VariableBindings::new(parent);
+        self.call_stack.push(StackFrame::new(
+            function.identifier.lexeme.clone(),
+            position,
+        ));
+        let return_value = self
+            .in_scope(scope, |interpreter| {
+                for (param, argument) in function.paramiters.iter().zip(arguments) {
+                    // The defaults are evaluated in the function's scope, so they can use the
+                    // parameters before them.
+                    let value = match (argument, &param.default) {
+                        (Some(value), _) => value,
+                        (None, Some(default)) => {
+                            let value = interpreter.evaluate_expression(default.clone())?;
+                            if value.is_interrupt() {
+                                return Ok(value);
+                            }
+                            value
+                        }
+                        (None, None) => Object::Nil(Meta::default()),
+                    };
+                    interpreter
+                        .variables
+                        .borrow_mut()
+                        .declare(&param.identifier, value);
+                }
+                interpreter.evaluate_block_expression(block_expression)
+            })
+            .map_err(|error| error.with_traceback(&self.call_stack));
+        self.call_stack.pop();
+
+        // The `return` stops at the function boundary, the caller gets a plain value.
+        let mut return_value = return_value?;
+        return_value.clear_interrupt();
         Ok(return_value)
     }
 
     fn evaluate_if_expression(&mut self, if_statement: IfExpression) -> Result<Object, Error> {
         let condition = self.evaluate_expression(*if_statement.condition)?;
+        if condition.is_interrupt() {
+            return Ok(condition);
+        }
         if condition.is_true() {
             self.evaluate_block_expression(if_statement.if_block)
         } else if let Some(else_block) = *if_statement.else_block {
@@ -125,21 +337,53 @@ impl Interpreter {
         &mut self,
         block_expression: BlockExpression,
     ) -> Result<Object, Error> {
-        let old_variables = self.variables.clone();
-        let mut return_value = Object::Nil(Meta::default());
-        for statement in *block_expression.statements {
-            if let Statement::Return(return_expression) = statement {
-                return_value = self.evaluate_expression(return_expression)?;
-                return_value.set_return();
-                break;
-            }
-            return_value = self.execute_statement(statement.clone())?;
-            if return_value.is_return() {
-                break;
+        let scope = VariableBindings::new(Rc::clone(&self.variables));
+        self.in_scope(scope, |interpreter| {
+            let mut return_value = Object::Nil(Meta::default());
+            for statement in *block_expression.statements {
+                if let Statement::Return(return_expression) = statement {
+                    return_value = interpreter.evaluate_expression(return_expression)?;
+                    return_value.set_return();
+                    break;
+                }
+                return_value = interpreter.execute_statement(statement)?;
+                if return_value.is_interrupt() {
+                    break;
+                }
             }
+            Ok(return_value)
+        })
+    }
+
+    /// Runs the construct inside of a return boundary, if it's the outermost one of the script.
+    fn bounded(
+        &mut self,
+        callback: impl FnOnce(&mut Self) -> Result<Object, Error>,
+    ) -> Result<Object, Error> {
+        if !self.call_stack.is_empty() || self.is_bounded {
+            return callback(self);
         }
-        self.variables = old_variables;
-        Ok(return_value)
+
+        self.is_bounded = true;
+        let result = callback(self);
+        self.is_bounded = false;
+        let mut value = result?;
+        if value.is_return() {
+            value.clear_interrupt();
+        }
+        Ok(value)
+    }
+
+    /// Runs the callback inside of the given scope. The current scope is restored, even if the callback fails.
+    fn in_scope<T>(
+        &mut self,
+        scope: Environment,
+        callback: impl FnOnce(&mut Self) -> Result<T, Error>,
+    ) -> Result<T, Error> {
+        let previous = std::mem::replace(&mut self.variables, scope);
+        let result = callback(self);
+        self.variables = previous;
+        result
     }
 
     fn evaluate_expression(&mut self, expression: Expression) -> Result<Object, Error> {
@@ -151,377 +395,21 @@ impl Interpreter {
         binary_expression: BinaryExpression,
     ) -> Result<Object, Error> {
         let left = self.match_expression(*binary_expression.left)?;
+        if left.is_interrupt() {
+            return Ok(left);
+        }
 
         let right = self.match_expression(*binary_expression.right)?;
-
-        match binary_expression.operator.ttype {
-            TokenType::And => Ok(Object::Boolean(
-                left.is_true() && right.is_true(),
-                Meta::default(),
-            )),
-
-            TokenType::Or => Ok(Object::Boolean(
-                left.is_true() || right.is_true(),
-                Meta::default(),
-            )),
-
-            TokenType::EqualEqual => Ok(Object::Boolean(left == right, Meta::default())),
-
-            TokenType::NotEqual => Ok(Object::Boolean(left != right, Meta::default())),
-
-            TokenType::Greater => match (left, right) {
-                (Object::Number(x, ..), Object::Number(y, ..)) => {
-                    Ok(Object::Boolean(x > y, Meta::default()))
-                }
-
-                (Object::Boolean(..), Object::Boolean(..)) => Err(Error::new(
-                    ErrorType::RuntimeError,
-                    format!(
-                        "Type mismatch, `{}` doesn't support `boolean` as it's operand",
-                        binary_expression.operator.lexeme
-                    ),
-                    binary_expression.operator.position,
-                )),
-
-                (Object::String(x, ..), Object::String(y, ..)) => {
-                    Ok(Object::String(x + &y, Meta::default()))
-                }
-
-                (Object::Nil(..), Object::Nil(..)) => Err(Error::new(
-                    ErrorType::RuntimeError,
-                    format!(
-                        "Type mismatch, `{}` doesn't support `nil` as it's operand",
-                        binary_expression.operator.lexeme
-                    ),
-                    binary_expression.operator.position,
-                )),
-
-                _ => Err(Error::new(
-                    ErrorType::RuntimeError,
-                    format!(
-                        "Type mismatch, `{}` expects same type on both side",
-                        binary_expression.operator.lexeme
-                    ),
-                    binary_expression.operator.position,
-                )),
-            },
-
-            TokenType::GreaterEqual => match (left, right) {
-                (Object::Number(x, ..), Object::Number(y, ..)) => {
-                    Ok(Object::Boolean(x >= y, Meta::default()))
-                }
-
-                (Object::Boolean(..), Object::Boolean(..)) => Err(Error::new(
-                    ErrorType::RuntimeError,
-                    format!(
-                        "Type mismatch, `{}` doesn't support `boolean` as it's operand",
-                        binary_expression.operator.lexeme
-                    ),
-                    binary_expression.operator.position,
-                )),
-
-                (Object::String(x, ..), Object::String(y, ..)) => {
-                    Ok(Object::String(x + &y, Meta::default()))
-                }
-
-                (Object::Nil(..), Object::Nil(..)) => Err(Error::new(
-                    ErrorType::RuntimeError,
-                    format!(
-                        "Type mismatch, `{}` doesn't support `nil` as it's operand",
-                        binary_expression.operator.lexeme
-                    ),
-                    binary_expression.operator.position,
-                )),
-
-                _ => Err(Error::new(
-                    ErrorType::RuntimeError,
-                    format!(
-                        "Type mismatch, `{}` expects same type on both side",
-                        binary_expression.operator.lexeme
-                    ),
-                    binary_expression.operator.position,
-                )),
-            },
-
-            TokenType::Less => match (left, right) {
-                (Object::Number(x, ..), Object::Number(y, ..)) => {
-                    Ok(Object::Boolean(x < y, Meta::default()))
-                }
-
-                (Object::Boolean(..), Object::Boolean(..)) => Err(Error::new(
-                    ErrorType::RuntimeError,
-                    format!(
-                        "Type mismatch, `{}` doesn't support `boolean` as it's operand",
-                        binary_expression.operator.lexeme
-                    ),
-                    binary_expression.operator.position,
-                )),
-
-                (Object::String(x, ..), Object::String(y, ..)) => {
-                    Ok(Object::String(x + &y, Meta::default()))
-                }
-
-                (Object::Nil(..), Object::Nil(..)) => Err(Error::new(
-                    ErrorType::RuntimeError,
-                    format!(
-                        "Type mismatch, `{}` doesn't support `nil` as it's operand",
-                        binary_expression.operator.lexeme
-                    ),
-                    binary_expression.operator.position,
-                )),
-
-                _ => Err(Error::new(
-                    ErrorType::RuntimeError,
-                    format!(
-                        "Type mismatch, `{}` expects same type on both side",
-                        binary_expression.operator.lexeme
-                    ),
-                    binary_expression.operator.position,
-                )),
-            },
-
-            TokenType::LessEqual => match (left, right) {
-                (Object::Number(x, ..), Object::Number(y, ..)) => {
-                    Ok(Object::Boolean(x <= y, Meta::default()))
-                }
-
-                (Object::Boolean(..), Object::Boolean(..)) => Err(Error::new(
-                    ErrorType::RuntimeError,
-                    format!(
-                        "Type mismatch, `{}` doesn't support `boolean` as it's operand",
-                        binary_expression.operator.lexeme
-                    ),
-                    binary_expression.operator.position,
-                )),
-
-                (Object::String(x, ..), Object::String(y, ..)) => {
-                    Ok(Object::String(x + &y, Meta::default()))
-                }
-
-                (Object::Nil(..), Object::Nil(..)) => Err(Error::new(
-                    ErrorType::RuntimeError,
-                    format!(
-                        "Type mismatch, `{}` doesn't support `nil` as it's operand",
-                        binary_expression.operator.lexeme
-                    ),
-                    binary_expression.operator.position,
-                )),
-
-                _ => Err(Error::new(
-                    ErrorType::RuntimeError,
-                    format!(
-                        "Type mismatch, `{}` expects same type on both side",
-                        binary_expression.operator.lexeme
-                    ),
-                    binary_expression.operator.position,
-                )),
-            },
-
-            TokenType::Plus => match (left, right) {
-                (Object::Number(x, ..), Object::Number(y, ..)) => {
-                    Ok(Object::Number(x + y, Meta::default()))
-                }
-
-                (Object::Boolean(..), Object::Boolean(..)) => Err(Error::new(
-                    ErrorType::RuntimeError,
-                    format!(
-                        "Type mismatch, `{}` doesn't support `boolean` as it's operand",
-                        binary_expression.operator.lexeme
-                    ),
-                    binary_expression.operator.position,
-                )),
-
-                (Object::String(x, ..), Object::String(y, ..)) => {
-                    Ok(Object::String(x + &y, Meta::default()))
-                }
-
-                (Object::Nil(..), Object::Nil(..)) => Err(Error::new(
-                    ErrorType::RuntimeError,
-                    format!(
-                        "Type mismatch, `{}` doesn't support `nil` as it's operand",
-                        binary_expression.operator.lexeme
-                    ),
-                    binary_expression.operator.position,
-                )),
-
-                _ => Err(Error::new(
-                    ErrorType::RuntimeError,
-                    format!(
-                        "Type mismatch, `{}` expects same type on both side",
-                        binary_expression.operator.lexeme
-                    ),
-                    binary_expression.operator.position,
-                )),
-            },
-
-            TokenType::Minus => match (left, right) {
-                (Object::Number(x, ..), Object::Number(y, ..)) => {
-                    Ok(Object::Number(x - y, Meta::default()))
-                }
-
-                (Object::Boolean(..), Object::Boolean(..)) => Err(Error::new(
-                    ErrorType::RuntimeError,
-                    format!(
-                        "Type mismatch, `{}` doesn't support `boolean` as it's operand",
-                        binary_expression.operator.lexeme
-                    ),
-                    binary_expression.operator.position,
-                )),
-
-                (Object::String(..), Object::String(..)) => Err(Error::new(
-                    ErrorType::RuntimeError,
-                    format!(
-                        "Type mismatch, `{}` doesn't support `string` as it's operand",
-                        binary_expression.operator.lexeme
-                    ),
-                    binary_expression.operator.position,
-                )),
-
-                (Object::Nil(..), Object::Nil(..)) => Err(Error::new(
-                    ErrorType::RuntimeError,
-                    format!(
-                        "Type mismatch, `{}` doesn't support `nil` as it's operand",
-                        binary_expression.operator.lexeme
-                    ),
-                    binary_expression.operator.position,
-                )),
-
-                _ => Err(Error::new(
-                    ErrorType::RuntimeError,
-                    format!(
-                        "Type mismatch, `{}` expects same type on both side",
-                        binary_expression.operator.lexeme
-                    ),
-                    binary_expression.operator.position,
-                )),
-            },
-
-            TokenType::Star => match (left, right) {
-                (Object::Number(x, ..), Object::Number(y, ..)) => {
-                    Ok(Object::Number(x * y, Meta::default()))
-                }
-
-                (Object::Boolean(..), Object::Boolean(..)) => Err(Error::new(
-                    ErrorType::RuntimeError,
-                    format!(
-                        "Type mismatch, `{}` doesn't support `boolean` as it's operand",
-                        binary_expression.operator.lexeme
-                    ),
-                    binary_expression.operator.position,
-                )),
-
-                (Object::String(..), Object::String(..)) => Err(Error::new(
-                    ErrorType::RuntimeError,
-                    format!(
-                        "Type mismatch, `{}` doesn't support `string` as it's operand",
-                        binary_expression.operator.lexeme
-                    ),
-                    binary_expression.operator.position,
-                )),
-
-                (Object::Nil(..), Object::Nil(..)) => Err(Error::new(
-                    ErrorType::RuntimeError,
-                    format!(
-                        "Type mismatch, `{}` doesn't support `nil` as it's operand",
-                        binary_expression.operator.lexeme
-                    ),
-                    binary_expression.operator.position,
-                )),
-
-                _ => Err(Error::new(
-                    ErrorType::RuntimeError,
-                    format!(
-                        "Type mismatch, `{}` expects same type on both side",
-                        binary_expression.operator.lexeme
-                    ),
-                    binary_expression.operator.position,
-                )),
-            },
-
-            TokenType::Slash => match (left, right) {
-                (Object::Number(x, ..), Object::Number(y, ..)) => {
-                    Ok(Object::Number(x / y, Meta::default()))
-                }
-
-                (Object::Boolean(..), Object::Boolean(..)) => Err(Error::new(
-                    ErrorType::RuntimeError,
-                    format!(
-                        "Type mismatch, `{}` doesn't support `boolean` as it's operand",
-                        binary_expression.operator.lexeme
-                    ),
-                    binary_expression.operator.position,
-                )),
-
-                (Object::String(..), Object::String(..)) => Err(Error::new(
-                    ErrorType::RuntimeError,
-                    format!(
-                        "Type mismatch, `{}` doesn't support `string` as it's operand",
-                        binary_expression.operator.lexeme
-                    ),
-                    binary_expression.operator.position,
-                )),
-
-                (Object::Nil(..), Object::Nil(..)) => Err(Error::new(
-                    ErrorType::RuntimeError,
-                    format!(
-                        "Type mismatch, `{}` doesn't support `nil` as it's operand",
-                        binary_expression.operator.lexeme
-                    ),
-                    binary_expression.operator.position,
-                )),
-
-                _ => Err(Error::new(
-                    ErrorType::RuntimeError,
-                    format!(
-                        "Type mismatch, `{}` expects same type on both side",
-                        binary_expression.operator.lexeme
-                    ),
-                    binary_expression.operator.position,
-                )),
-            },
-
-            TokenType::Modulo => match (left, right) {
-                (Object::Number(x, ..), Object::Number(y, ..)) => {
-                    Ok(Object::Number(x % y, Meta::default()))
-                }
-
-                (Object::String(..), Object::String(..)) => Err(Error::new(
-                    ErrorType::RuntimeError,
-                    format!(
-                        "Type mismatch, `{}` doesn't support `string` as it's operand",
-                        binary_expression.operator.lexeme
-                    ),
-                    binary_expression.operator.position,
-                )),
-
-                (Object::Nil(..), Object::Nil(..)) => Err(Error::new(
-                    ErrorType::RuntimeError,
-                    format!(
-                        "Type mismatch, `{}` doesn't support `nil` as it's operand",
-                        binary_expression.operator.lexeme
-                    ),
-                    binary_expression.operator.position,
-                )),
-
-                _ => Err(Error::new(
-                    ErrorType::RuntimeError,
-                    format!(
-                        "Type mismatch, `{}` expects same type on both side",
-                        binary_expression.operator.lexeme
-                    ),
-                    binary_expression.operator.position,
-                )),
-            },
-
-            _ => Err(Error::new(
-                ErrorType::RuntimeError,
-                format!(
-                    "`{}` is not a binary operator.",
-                    binary_expression.operator.lexeme
-                ),
-                binary_expression.operator.position,
-            )),
+        if right.is_interrupt() {
+            return Ok(right);
         }
+
+        operator::binary(
+            &binary_expression.operator.ttype,
+            left,
+            right,
+            binary_expression.operator.position,
+        )
     }
 
     fn evaluate_unary_expression(
@@ -529,59 +417,15 @@ impl Interpreter {
         unary_expression: UnaryExpression,
     ) -> Result<Object, Error> {
         let right = self.match_expression(*unary_expression.right)?;
-
-        match unary_expression.operator.ttype {
-            TokenType::Not => Ok(Object::Boolean(!right.is_true(), Meta::default())),
-
-            TokenType::Minus => match right {
-                Object::Number(x, ..) => Ok(Object::Number(x * -1., Meta::default())),
-
-                Object::Boolean(..) => Err(Error::new(
-                    ErrorType::RuntimeError,
-                    format!(
-                        "Type mismatch, `{}` does not support `boolean` as it's operand",
-                        unary_expression.operator.lexeme
-                    ),
-                    unary_expression.operator.position,
-                )),
-
-                Object::String(..) => Err(Error::new(
-                    ErrorType::RuntimeError,
-                    format!(
-                        "Type mismatch, `{}` does not support `string` as it's operand",
-                        unary_expression.operator.lexeme
-                    ),
-                    unary_expression.operator.position,
-                )),
-
-                Object::Nil(..) => Err(Error::new(
-                    ErrorType::RuntimeError,
-                    format!(
-                        "Type mismatch, `{}` does not support `nil` as it's operand",
-                        unary_expression.operator.lexeme
-                    ),
-                    unary_expression.operator.position,
-                )),
-
-                Object::Array(..) => Err(Error::new(
-                    ErrorType::RuntimeError,
-                    format!(
-                        "Type mismatch, `{}` does not support `array` as it's operand",
-                        unary_expression.operator.lexeme
-                    ),
-                    unary_expression.operator.position,
-                )),
-            },
-
-            _ => Err(Error::new(
-                ErrorType::RuntimeError,
-                format!(
-                    "`{}` is not a unary operator.",
-                    unary_expression.operator.lexeme
-                ),
-                unary_expression.operator.position,
-            )),
+        if right.is_interrupt() {
+            return Ok(right);
         }
+
+        operator::unary(
+            &unary_expression.operator.ttype,
+            right,
+            unary_expression.operator.position,
+        )
     }
 
     fn evaluate_group_expression(
@@ -596,42 +440,107 @@ impl Interpreter {
         &mut self,
         call_expression: CallExpression,
     ) -> Result<Object, Error> {
-        let function_statement = self.functions.get(call_expression.identifier.clone())?;
-        let paramiters = function_statement.paramiters.clone();
-        let arguments_length = call_expression.arguments.len();
-        match arguments_length.cmp(&paramiters.len()) {
-            Ordering::Less => {
-                return Err(Error::new(
-                    ErrorType::RuntimeError,
-                    format!(
-                        "The `{}` expected {} arguments but got {}. Missing arguments are {}",
-                        call_expression.identifier.lexeme,
-                        paramiters.len(),
-                        arguments_length,
-                        paramiters[arguments_length..]
-                            .iter()
-                            .map(|p| format!("`{}`", p.identifier.lexeme))
-                            .collect::<Vec<_>>()
-                            .join(", ")
+        let callee = match *call_expression.callee {
+            // A name that isn't a variable is looked up among the functions.
+            Expression::Identifier(identifier_expression) => {
+                let variable = self
+                    .variables
+                    .borrow()
+                    .get(&identifier_expression.identifier);
+                match variable {
+                    Ok(object) => object,
+                    Err(_) => Object::Function(
+                        self.functions.get(&identifier_expression.identifier)?,
+                        Meta::default(),
                     ),
-                    call_expression.identifier.position,
-                ))
+                }
             }
-            Ordering::Greater => {
+            callee => {
+                let callee = self.evaluate_expression(callee)?;
+                if callee.is_interrupt() {
+                    return Ok(callee);
+                }
+                callee
+            }
+        };
+        let function = match callee {
+            Object::Function(function, ..) => function,
+            object => {
                 return Err(Error::new(
                     ErrorType::RuntimeError,
-                    format!(
-                        "too many arguments passed to `{}`. Expected {} but got {}",
-                        call_expression.identifier.lexeme,
-                        paramiters.len(),
-                        arguments_length
-                    ),
-                    call_expression.identifier.position,
+                    format!("`{}` is not callable", object),
+                    call_expression.position,
                 ))
             }
-            Ordering::Equal => {
-                self.execute_function_statement(call_expression.arguments, function_statement)
+        };
+
+        let (names, named): (Vec<_>, Vec<_>) = call_expression.named.into_iter().unzip();
+        function.check_arity(
+            call_expression.arguments.len(),
+            &names,
+            call_expression.position.clone(),
+        )?;
+        let mut arguments = Vec::new();
+        for argument in call_expression.arguments.into_iter().chain(named) {
+            // A `return`, `break` or `continue` in an argument leaves before the call is made.
+            let argument = self.evaluate_expression(argument)?;
+            if argument.is_interrupt() {
+                return Ok(argument);
             }
+            arguments.push(argument);
+        }
+        let arguments = function.bind_arguments(arguments, &names);
+        self.execute_function(&function, arguments, call_expression.position)
+    }
+
+    fn evaluate_range_expression(
+        &mut self,
+        range_expression: RangeExpression,
+    ) -> Result<Object, Error> {
+        let start = self.evaluate_expression(*range_expression.start)?;
+        if start.is_interrupt() {
+            return Ok(start);
+        }
+        let end = self.evaluate_expression(*range_expression.end)?;
+        if end.is_interrupt() {
+            return Ok(end);
+        }
+
+        operator::range(
+            start,
+            end,
+            &range_expression.operator.ttype,
+            range_expression.operator.position,
+        )
+    }
+
+    fn evaluate_index_expression(
+        &mut self,
+        index_expression: IndexExpression,
+    ) -> Result<Object, Error> {
+        let object = self.evaluate_expression(*index_expression.object)?;
+        if object.is_interrupt() {
+            return Ok(object);
+        }
+        let position = index_expression.index.position();
+
+        if let Expression::Range(range_expression) = *index_expression.index {
+            let inclusive = range_expression.is_inclusive();
+            let start = self.evaluate_expression(*range_expression.start)?;
+            if start.is_interrupt() {
+                return Ok(start);
+            }
+            let end = self.evaluate_expression(*range_expression.end)?;
+            if end.is_interrupt() {
+                return Ok(end);
+            }
+            object.slice(&start, &end, inclusive, position)
+        } else {
+            let index = self.evaluate_expression(*index_expression.index)?;
+            if index.is_interrupt() {
+                return Ok(index);
+            }
+            object.index(&index, position)
         }
     }
 
@@ -639,7 +548,15 @@ impl Interpreter {
         &self,
         identifier_expression: IdentifierExpression,
     ) -> Result<Object, Error> {
-        self.variables.get(identifier_expression.identifier)
+        let identifier = identifier_expression.identifier;
+        // A function name evaluates to the function itself, so it can be passed around.
+        let variable = self.variables.borrow().get(&identifier);
+        variable.or_else(|error| {
+            self.functions
+                .get(&identifier)
+                .map(|function| Object::Function(function, Meta::default()))
+                .map_err(|_| error)
+        })
     }
 
     fn match_expression(&mut self, expression: Expression) -> Result<Object, Error> {
@@ -658,9 +575,21 @@ impl Interpreter {
                 Ok(self.evaluate_identifier_expression(identifier_expression)?)
             }
 
-            Expression::Block(block_expression) => self.evaluate_block_expression(block_expression),
+            Expression::Block(block_expression) => {
+                self.bounded(|interpreter| interpreter.evaluate_block_expression(block_expression))
+            }
 
-            Expression::If(if_expression) => self.evaluate_if_expression(if_expression),
+            Expression::Function(function_statement) => Ok(Object::Function(
+                Rc::new(Function::new(
+                    function_statement,
+                    Some(Closure::capture(Rc::clone(&self.variables), None)),
+                )),
+                Meta::default(),
+            )),
+
+            Expression::If(if_expression) => {
+                self.bounded(|interpreter| interpreter.evaluate_if_expression(if_expression))
+            }
 
             Expression::Literal(literal_expression) => {
                 if let Some(object) = literal_expression.object.literal {
@@ -670,17 +599,50 @@ impl Interpreter {
                 }
             }
 
+            Expression::Interpolation(interpolation_expression) => {
+                let mut string = String::new();
+                for part in interpolation_expression.parts {
+                    let object = self.evaluate_expression(part)?;
+                    if object.is_interrupt() {
+                        return Ok(object);
+                    }
+                    string.push_str(&object.to_string());
+                }
+                Ok(Object::String(string, Meta::default()))
+            }
+
+            Expression::Range(range_expression) => self.evaluate_range_expression(range_expression),
+
+            Expression::Index(index_expression) => self.evaluate_index_expression(index_expression),
+
             Expression::Array(array_expression) => {
                 let mut objects = Vec::new();
-                for object in array_expression.objects {
-                    if let Some(object) = object.literal {
-                        objects.push(object)
-                    } else if matches!(object.ttype, TokenType::Identifier) {
-                        objects.push(self.variables.get(object)?)
+                for element in array_expression.elements {
+                    let object = self.evaluate_expression(element)?;
+                    if object.is_interrupt() {
+                        return Ok(object);
                     }
+                    objects.push(object);
                 }
                 Ok(Object::Array(objects, Meta::default()))
             }
+
+            Expression::Map(map_expression) => {
+                let mut map = BTreeMap::new();
+                for (key, value) in map_expression.entries {
+                    let position = key.position();
+                    let key = self.evaluate_expression(key)?;
+                    if key.is_interrupt() {
+                        return Ok(key);
+                    }
+                    let value = self.evaluate_expression(value)?;
+                    if value.is_interrupt() {
+                        return Ok(value);
+                    }
+                    map.insert(Object::key(&key, &position)?, value);
+                }
+                Ok(Object::Map(map, Meta::default()))
+            }
         }
     }
 }
@@ -1,3 +1,6 @@
-mod environment;
+pub mod builtin;
+pub mod compiler;
+pub mod environment;
 pub mod interpreter;
-pub mod builtin;
\ No newline at end of file
+pub mod operator;
+pub mod vm;
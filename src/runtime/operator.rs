@@ -0,0 +1,907 @@
+use std::{cmp::Ordering, ops::RangeInclusive, vec::IntoIter};
+
+use crate::common::{
+    error::{Error, ErrorType},
+    number::{compare_integer_float, BigInt, Decimal},
+    object::{Meta, Object},
+    position::Position,
+    token::TokenType,
+};
+
+// The semantics of the operators, shared by the interpreter and the virtual machine.
+
+pub fn binary(
+    operator: &TokenType,
+    left: Object,
+    right: Object,
+    position: Position,
+) -> Result<Object, Error> {
+    if let Some(result) = compare_exactly(operator, &left, &right) {
+        return Ok(Object::Boolean(result, Meta::default()));
+    }
+    let (left, right) = promote(left, right);
+    if is_inexact_mix(&left, &right)
+        && !matches!(
+            operator,
+            TokenType::And | TokenType::Or | TokenType::EqualEqual | TokenType::NotEqual
+        )
+    {
+        return Err(Error::new(
+            ErrorType::RuntimeError,
+            format!(
+                "Type mismatch, `{}` can't mix a float with an exact number",
+                operator
+            ),
+            position,
+        )
+        .with_help(
+            "convert the float with `decimal()`, or the other side with `float()`".to_string(),
+        ));
+    }
+    match operator {
+        TokenType::And => Ok(Object::Boolean(
+            left.is_true() && right.is_true(),
+            Meta::default(),
+        )),
+
+        TokenType::Or => Ok(Object::Boolean(
+            left.is_true() || right.is_true(),
+            Meta::default(),
+        )),
+
+        TokenType::EqualEqual => Ok(Object::Boolean(left == right, Meta::default())),
+
+        TokenType::NotEqual => Ok(Object::Boolean(left != right, Meta::default())),
+
+        TokenType::Greater => match (left, right) {
+            (Object::Integer(x, ..), Object::Integer(y, ..)) => {
+                Ok(Object::Boolean(x > y, Meta::default()))
+            }
+
+            (Object::BigInt(x, ..), Object::BigInt(y, ..)) => {
+                Ok(Object::Boolean(x > y, Meta::default()))
+            }
+
+            (Object::Decimal(x, ..), Object::Decimal(y, ..)) => {
+                Ok(Object::Boolean(x > y, Meta::default()))
+            }
+
+            (Object::Number(x, ..), Object::Number(y, ..)) => {
+                Ok(Object::Boolean(x > y, Meta::default()))
+            }
+
+            (Object::Boolean(..), Object::Boolean(..)) => Err(Error::new(
+                ErrorType::RuntimeError,
+                format!(
+                    "Type mismatch, `{}` doesn't support `boolean` as it's operand",
+                    operator
+                ),
+                position,
+            )),
+
+            (Object::String(x, ..), Object::String(y, ..)) => {
+                Ok(Object::String(x + &y, Meta::default()))
+            }
+
+            (Object::Nil(..), Object::Nil(..)) => Err(Error::new(
+                ErrorType::RuntimeError,
+                format!(
+                    "Type mismatch, `{}` doesn't support `nil` as it's operand",
+                    operator
+                ),
+                position,
+            )),
+
+            _ => Err(Error::new(
+                ErrorType::RuntimeError,
+                format!(
+                    "Type mismatch, `{}` expects same type on both side",
+                    operator
+                ),
+                position,
+            )),
+        },
+
+        TokenType::GreaterEqual => match (left, right) {
+            (Object::Integer(x, ..), Object::Integer(y, ..)) => {
+                Ok(Object::Boolean(x >= y, Meta::default()))
+            }
+
+            (Object::BigInt(x, ..), Object::BigInt(y, ..)) => {
+                Ok(Object::Boolean(x >= y, Meta::default()))
+            }
+
+            (Object::Decimal(x, ..), Object::Decimal(y, ..)) => {
+                Ok(Object::Boolean(x >= y, Meta::default()))
+            }
+
+            (Object::Number(x, ..), Object::Number(y, ..)) => {
+                Ok(Object::Boolean(x >= y, Meta::default()))
+            }
+
+            (Object::Boolean(..), Object::Boolean(..)) => Err(Error::new(
+                ErrorType::RuntimeError,
+                format!(
+                    "Type mismatch, `{}` doesn't support `boolean` as it's operand",
+                    operator
+                ),
+                position,
+            )),
+
+            (Object::String(x, ..), Object::String(y, ..)) => {
+                Ok(Object::String(x + &y, Meta::default()))
+            }
+
+            (Object::Nil(..), Object::Nil(..)) => Err(Error::new(
+                ErrorType::RuntimeError,
+                format!(
+                    "Type mismatch, `{}` doesn't support `nil` as it's operand",
+                    operator
+                ),
+                position,
+            )),
+
+            _ => Err(Error::new(
+                ErrorType::RuntimeError,
+                format!(
+                    "Type mismatch, `{}` expects same type on both side",
+                    operator
+                ),
+                position,
+            )),
+        },
+
+        TokenType::Less => match (left, right) {
+            (Object::Integer(x, ..), Object::Integer(y, ..)) => {
+                Ok(Object::Boolean(x < y, Meta::default()))
+            }
+
+            (Object::BigInt(x, ..), Object::BigInt(y, ..)) => {
+                Ok(Object::Boolean(x < y, Meta::default()))
+            }
+
+            (Object::Decimal(x, ..), Object::Decimal(y, ..)) => {
+                Ok(Object::Boolean(x < y, Meta::default()))
+            }
+
+            (Object::Number(x, ..), Object::Number(y, ..)) => {
+                Ok(Object::Boolean(x < y, Meta::default()))
+            }
+
+            (Object::Boolean(..), Object::Boolean(..)) => Err(Error::new(
+                ErrorType::RuntimeError,
+                format!(
+                    "Type mismatch, `{}` doesn't support `boolean` as it's operand",
+                    operator
+                ),
+                position,
+            )),
+
+            (Object::String(x, ..), Object::String(y, ..)) => {
+                Ok(Object::String(x + &y, Meta::default()))
+            }
+
+            (Object::Nil(..), Object::Nil(..)) => Err(Error::new(
+                ErrorType::RuntimeError,
+                format!(
+                    "Type mismatch, `{}` doesn't support `nil` as it's operand",
+                    operator
+                ),
+                position,
+            )),
+
+            _ => Err(Error::new(
+                ErrorType::RuntimeError,
+                format!(
+                    "Type mismatch, `{}` expects same type on both side",
+                    operator
+                ),
+                position,
+            )),
+        },
+
+        TokenType::LessEqual => match (left, right) {
+            (Object::Integer(x, ..), Object::Integer(y, ..)) => {
+                Ok(Object::Boolean(x <= y, Meta::default()))
+            }
+
+            (Object::BigInt(x, ..), Object::BigInt(y, ..)) => {
+                Ok(Object::Boolean(x <= y, Meta::default()))
+            }
+
+            (Object::Decimal(x, ..), Object::Decimal(y, ..)) => {
+                Ok(Object::Boolean(x <= y, Meta::default()))
+            }
+
+            (Object::Number(x, ..), Object::Number(y, ..)) => {
+                Ok(Object::Boolean(x <= y, Meta::default()))
+            }
+
+            (Object::Boolean(..), Object::Boolean(..)) => Err(Error::new(
+                ErrorType::RuntimeError,
+                format!(
+                    "Type mismatch, `{}` doesn't support `boolean` as it's operand",
+                    operator
+                ),
+                position,
+            )),
+
+            (Object::String(x, ..), Object::String(y, ..)) => {
+                Ok(Object::String(x + &y, Meta::default()))
+            }
+
+            (Object::Nil(..), Object::Nil(..)) => Err(Error::new(
+                ErrorType::RuntimeError,
+                format!(
+                    "Type mismatch, `{}` doesn't support `nil` as it's operand",
+                    operator
+                ),
+                position,
+            )),
+
+            _ => Err(Error::new(
+                ErrorType::RuntimeError,
+                format!(
+                    "Type mismatch, `{}` expects same type on both side",
+                    operator
+                ),
+                position,
+            )),
+        },
+
+        TokenType::Plus => match (left, right) {
+            (Object::Integer(x, ..), Object::Integer(y, ..)) => {
+                integer(x.checked_add(y), operator, position)
+            }
+
+            (Object::BigInt(x, ..), Object::BigInt(y, ..)) => {
+                Ok(Object::BigInt(&x + &y, Meta::default()))
+            }
+
+            (Object::Decimal(x, ..), Object::Decimal(y, ..)) => {
+                Ok(Object::Decimal(&x + &y, Meta::default()))
+            }
+
+            (Object::Number(x, ..), Object::Number(y, ..)) => {
+                Ok(Object::Number(x + y, Meta::default()))
+            }
+
+            (Object::Boolean(..), Object::Boolean(..)) => Err(Error::new(
+                ErrorType::RuntimeError,
+                format!(
+                    "Type mismatch, `{}` doesn't support `boolean` as it's operand",
+                    operator
+                ),
+                position,
+            )),
+
+            (Object::String(x, ..), Object::String(y, ..)) => {
+                Ok(Object::String(x + &y, Meta::default()))
+            }
+
+            (Object::Nil(..), Object::Nil(..)) => Err(Error::new(
+                ErrorType::RuntimeError,
+                format!(
+                    "Type mismatch, `{}` doesn't support `nil` as it's operand",
+                    operator
+                ),
+                position,
+            )),
+
+            _ => Err(Error::new(
+                ErrorType::RuntimeError,
+                format!(
+                    "Type mismatch, `{}` expects same type on both side",
+                    operator
+                ),
+                position,
+            )),
+        },
+
+        TokenType::Minus => match (left, right) {
+            (Object::Integer(x, ..), Object::Integer(y, ..)) => {
+                integer(x.checked_sub(y), operator, position)
+            }
+
+            (Object::BigInt(x, ..), Object::BigInt(y, ..)) => {
+                Ok(Object::BigInt(&x - &y, Meta::default()))
+            }
+
+            (Object::Decimal(x, ..), Object::Decimal(y, ..)) => {
+                Ok(Object::Decimal(&x - &y, Meta::default()))
+            }
+
+            (Object::Number(x, ..), Object::Number(y, ..)) => {
+                Ok(Object::Number(x - y, Meta::default()))
+            }
+
+            (Object::Boolean(..), Object::Boolean(..)) => Err(Error::new(
+                ErrorType::RuntimeError,
+                format!(
+                    "Type mismatch, `{}` doesn't support `boolean` as it's operand",
+                    operator
+                ),
+                position,
+            )),
+
+            (Object::String(..), Object::String(..)) => Err(Error::new(
+                ErrorType::RuntimeError,
+                format!(
+                    "Type mismatch, `{}` doesn't support `string` as it's operand",
+                    operator
+                ),
+                position,
+            )),
+
+            (Object::Nil(..), Object::Nil(..)) => Err(Error::new(
+                ErrorType::RuntimeError,
+                format!(
+                    "Type mismatch, `{}` doesn't support `nil` as it's operand",
+                    operator
+                ),
+                position,
+            )),
+
+            _ => Err(Error::new(
+                ErrorType::RuntimeError,
+                format!(
+                    "Type mismatch, `{}` expects same type on both side",
+                    operator
+                ),
+                position,
+            )),
+        },
+
+        TokenType::Star => match (left, right) {
+            (Object::Integer(x, ..), Object::Integer(y, ..)) => {
+                integer(x.checked_mul(y), operator, position)
+            }
+
+            (Object::BigInt(x, ..), Object::BigInt(y, ..)) => {
+                Ok(Object::BigInt(&x * &y, Meta::default()))
+            }
+
+            (Object::Decimal(x, ..), Object::Decimal(y, ..)) => {
+                Ok(Object::Decimal(&x * &y, Meta::default()))
+            }
+
+            (Object::Number(x, ..), Object::Number(y, ..)) => {
+                Ok(Object::Number(x * y, Meta::default()))
+            }
+
+            (Object::Boolean(..), Object::Boolean(..)) => Err(Error::new(
+                ErrorType::RuntimeError,
+                format!(
+                    "Type mismatch, `{}` doesn't support `boolean` as it's operand",
+                    operator
+                ),
+                position,
+            )),
+
+            (Object::String(..), Object::String(..)) => Err(Error::new(
+                ErrorType::RuntimeError,
+                format!(
+                    "Type mismatch, `{}` doesn't support `string` as it's operand",
+                    operator
+                ),
+                position,
+            )),
+
+            (Object::Nil(..), Object::Nil(..)) => Err(Error::new(
+                ErrorType::RuntimeError,
+                format!(
+                    "Type mismatch, `{}` doesn't support `nil` as it's operand",
+                    operator
+                ),
+                position,
+            )),
+
+            _ => Err(Error::new(
+                ErrorType::RuntimeError,
+                format!(
+                    "Type mismatch, `{}` expects same type on both side",
+                    operator
+                ),
+                position,
+            )),
+        },
+
+        TokenType::Slash => match (left, right) {
+            (Object::Integer(..), Object::Integer(0, ..)) => Err(division_by_zero(position)),
+
+            // The quotient stays an integer only if the division is exact, e.g. `6 / 3` but not `7 / 2`.
+            // `MIN % -1` can't be computed, but it's exact, so it's left to `checked_div` to overflow.
+            (Object::Integer(x, ..), Object::Integer(y, ..)) if x.wrapping_rem(y) != 0 => {
+                Ok(Object::Number(x as f64 / y as f64, Meta::default()))
+            }
+
+            (Object::Integer(x, ..), Object::Integer(y, ..)) => {
+                integer(x.checked_div(y), operator, position)
+            }
+
+            // Like integers, the quotient of big integers is only exact if the division is, it's a
+            // decimal otherwise.
+            (Object::BigInt(x, ..), Object::BigInt(y, ..)) => match x.div_rem(&y) {
+                Some((quotient, remainder)) if remainder.is_zero() => {
+                    Ok(Object::BigInt(quotient, Meta::default()))
+                }
+                Some(_) => Decimal::from(x)
+                    .checked_div(&Decimal::from(y))
+                    .map(|decimal| Object::Decimal(decimal, Meta::default()))
+                    .ok_or_else(|| division_by_zero(position)),
+                None => Err(division_by_zero(position)),
+            },
+
+            (Object::Decimal(x, ..), Object::Decimal(y, ..)) => x
+                .checked_div(&y)
+                .map(|decimal| Object::Decimal(decimal, Meta::default()))
+                .ok_or_else(|| division_by_zero(position)),
+
+            (Object::Number(x, ..), Object::Number(y, ..)) => {
+                Ok(Object::Number(x / y, Meta::default()))
+            }
+
+            (Object::Boolean(..), Object::Boolean(..)) => Err(Error::new(
+                ErrorType::RuntimeError,
+                format!(
+                    "Type mismatch, `{}` doesn't support `boolean` as it's operand",
+                    operator
+                ),
+                position,
+            )),
+
+            (Object::String(..), Object::String(..)) => Err(Error::new(
+                ErrorType::RuntimeError,
+                format!(
+                    "Type mismatch, `{}` doesn't support `string` as it's operand",
+                    operator
+                ),
+                position,
+            )),
+
+            (Object::Nil(..), Object::Nil(..)) => Err(Error::new(
+                ErrorType::RuntimeError,
+                format!(
+                    "Type mismatch, `{}` doesn't support `nil` as it's operand",
+                    operator
+                ),
+                position,
+            )),
+
+            _ => Err(Error::new(
+                ErrorType::RuntimeError,
+                format!(
+                    "Type mismatch, `{}` expects same type on both side",
+                    operator
+                ),
+                position,
+            )),
+        },
+
+        TokenType::Modulo => match (left, right) {
+            (Object::Integer(..), Object::Integer(0, ..)) => Err(division_by_zero(position)),
+
+            // `MIN % -1` is 0, only the quotient of it overflows.
+            (Object::Integer(x, ..), Object::Integer(y, ..)) => {
+                Ok(Object::Integer(x.wrapping_rem(y), Meta::default()))
+            }
+
+            (Object::BigInt(x, ..), Object::BigInt(y, ..)) => x
+                .div_rem(&y)
+                .map(|(_, remainder)| Object::BigInt(remainder, Meta::default()))
+                .ok_or_else(|| division_by_zero(position)),
+
+            (Object::Decimal(x, ..), Object::Decimal(y, ..)) => x
+                .checked_rem(&y)
+                .map(|decimal| Object::Decimal(decimal, Meta::default()))
+                .ok_or_else(|| division_by_zero(position)),
+
+            (Object::Number(x, ..), Object::Number(y, ..)) => {
+                Ok(Object::Number(x % y, Meta::default()))
+            }
+
+            (Object::String(..), Object::String(..)) => Err(Error::new(
+                ErrorType::RuntimeError,
+                format!(
+                    "Type mismatch, `{}` doesn't support `string` as it's operand",
+                    operator
+                ),
+                position,
+            )),
+
+            (Object::Nil(..), Object::Nil(..)) => Err(Error::new(
+                ErrorType::RuntimeError,
+                format!(
+                    "Type mismatch, `{}` doesn't support `nil` as it's operand",
+                    operator
+                ),
+                position,
+            )),
+
+            _ => Err(Error::new(
+                ErrorType::RuntimeError,
+                format!(
+                    "Type mismatch, `{}` expects same type on both side",
+                    operator
+                ),
+                position,
+            )),
+        },
+
+        _ => Err(Error::new(
+            ErrorType::RuntimeError,
+            format!("`{}` is not a binary operator.", operator),
+            position,
+        )),
+    }
+}
+
+pub fn unary(operator: &TokenType, right: Object, position: Position) -> Result<Object, Error> {
+    match operator {
+        TokenType::Not => Ok(Object::Boolean(!right.is_true(), Meta::default())),
+
+        TokenType::Minus => match right {
+            Object::Integer(x, ..) => integer(x.checked_neg(), operator, position),
+
+            Object::BigInt(x, ..) => Ok(Object::BigInt(-&x, Meta::default())),
+
+            Object::Decimal(x, ..) => Ok(Object::Decimal(-&x, Meta::default())),
+
+            Object::Number(x, ..) => Ok(Object::Number(-x, Meta::default())),
+
+            Object::Boolean(..) => Err(Error::new(
+                ErrorType::RuntimeError,
+                format!(
+                    "Type mismatch, `{}` does not support `boolean` as it's operand",
+                    operator
+                ),
+                position,
+            )),
+
+            Object::String(..) => Err(Error::new(
+                ErrorType::RuntimeError,
+                format!(
+                    "Type mismatch, `{}` does not support `string` as it's operand",
+                    operator
+                ),
+                position,
+            )),
+
+            Object::Nil(..) => Err(Error::new(
+                ErrorType::RuntimeError,
+                format!(
+                    "Type mismatch, `{}` does not support `nil` as it's operand",
+                    operator
+                ),
+                position,
+            )),
+
+            Object::Array(..) => Err(Error::new(
+                ErrorType::RuntimeError,
+                format!(
+                    "Type mismatch, `{}` does not support `array` as it's operand",
+                    operator
+                ),
+                position,
+            )),
+            Object::Map(..) => Err(Error::new(
+                ErrorType::RuntimeError,
+                format!(
+                    "Type mismatch, `{}` does not support `map` as it's operand",
+                    operator
+                ),
+                position,
+            )),
+            Object::Function(..) => Err(Error::new(
+                ErrorType::RuntimeError,
+                format!(
+                    "Type mismatch, `{}` does not support `function` as it's operand",
+                    operator
+                ),
+                position,
+            )),
+        },
+
+        _ => Err(Error::new(
+            ErrorType::RuntimeError,
+            format!("`{}` is not a unary operator.", operator),
+            position,
+        )),
+    }
+}
+
+// The narrower of two numbers is turned into the wider one, so the operators only see the same
+// types. An integer widens to a float, a big integer or a decimal, and a big integer to a decimal.
+fn promote(left: Object, right: Object) -> (Object, Object) {
+    match (left, right) {
+        (Object::Integer(x, ..), right @ Object::BigInt(..)) => {
+            (Object::BigInt(BigInt::from(x), Meta::default()), right)
+        }
+        (left @ Object::BigInt(..), Object::Integer(y, ..)) => {
+            (left, Object::BigInt(BigInt::from(y), Meta::default()))
+        }
+        (Object::Integer(x, ..), right @ Object::Decimal(..)) => (
+            Object::Decimal(Decimal::from(BigInt::from(x)), Meta::default()),
+            right,
+        ),
+        (left @ Object::Decimal(..), Object::Integer(y, ..)) => (
+            left,
+            Object::Decimal(Decimal::from(BigInt::from(y)), Meta::default()),
+        ),
+        (Object::BigInt(x, ..), right @ Object::Decimal(..)) => {
+            (Object::Decimal(Decimal::from(x), Meta::default()), right)
+        }
+        (left @ Object::Decimal(..), Object::BigInt(y, ..)) => {
+            (left, Object::Decimal(Decimal::from(y), Meta::default()))
+        }
+        (Object::Integer(x, ..), Object::Number(y, ..)) => (
+            Object::Number(x as f64, Meta::default()),
+            Object::Number(y, Meta::default()),
+        ),
+        (Object::Number(x, ..), Object::Integer(y, ..)) => (
+            Object::Number(x, Meta::default()),
+            Object::Number(y as f64, Meta::default()),
+        ),
+        pair => pair,
+    }
+}
+
+// An integer and a float are compared exactly, instead of turning the integer into a float that
+// can't hold it, e.g. `9007199254740993 > 9007199254740992.0` is true.
+fn compare_exactly(operator: &TokenType, left: &Object, right: &Object) -> Option<bool> {
+    let ordering = match (left, right) {
+        (Object::Integer(x, ..), Object::Number(y, ..)) => compare_integer_float(*x, *y),
+        (Object::Number(x, ..), Object::Integer(y, ..)) => {
+            compare_integer_float(*y, *x).map(Ordering::reverse)
+        }
+        _ => return None,
+    };
+    let is =
+        |expected: &[Ordering]| matches!(ordering, Some(ordering) if expected.contains(&ordering));
+    match operator {
+        TokenType::EqualEqual => Some(is(&[Ordering::Equal])),
+        TokenType::NotEqual => Some(!is(&[Ordering::Equal])),
+        TokenType::Greater => Some(is(&[Ordering::Greater])),
+        TokenType::GreaterEqual => Some(is(&[Ordering::Greater, Ordering::Equal])),
+        TokenType::Less => Some(is(&[Ordering::Less])),
+        TokenType::LessEqual => Some(is(&[Ordering::Less, Ordering::Equal])),
+        _ => None,
+    }
+}
+
+// A float is never turned into an exact number or the other way around, the float would lose the
+// exactness silently, e.g. `0.1 + 0.2`.
+fn is_inexact_mix(left: &Object, right: &Object) -> bool {
+    let is_exact = |object: &Object| matches!(object, Object::BigInt(..) | Object::Decimal(..));
+    matches!(
+        (left, right),
+        (Object::Number(..), other) | (other, Object::Number(..)) if is_exact(other)
+    )
+}
+
+// The result of a checked integer operation, None means it didn't fit in 64 bits.
+fn integer(result: Option<i64>, operator: &TokenType, position: Position) -> Result<Object, Error> {
+    result
+        .map(|integer| Object::Integer(integer, Meta::default()))
+        .ok_or_else(|| {
+            Error::new(
+                ErrorType::RuntimeError,
+                format!(
+                    "Integer overflow, the result of `{}` doesn't fit in 64 bits",
+                    operator
+                ),
+                position,
+            )
+            .with_note(format!(
+                "integers are between {} and {}",
+                i64::MIN,
+                i64::MAX
+            ))
+            .with_help("convert one of the operands with `float()` to get a float".to_string())
+        })
+}
+
+fn division_by_zero(position: Position) -> Error {
+    Error::new(
+        ErrorType::RuntimeError,
+        "Division by zero".to_string(),
+        position,
+    )
+}
+
+// Past 2^53 a float can't tell `x` and `x + 1` apart, so a range there would never end.
+const MAX_EXACT_FLOAT: f64 = 9_007_199_254_740_992.;
+
+// The most items an array built from a range can have, a `for` loop counts larger ranges lazily.
+const MAX_RANGE_ITEMS: u128 = 10_000_000;
+
+/// The items a `for` loop walks through. A range is counted as the loop goes, so it's never built
+/// up front.
+pub enum Items {
+    Objects(IntoIter<Object>),
+    Integers(RangeInclusive<i64>),
+    Floats {
+        current: f64,
+        end: f64,
+        inclusive: bool,
+    },
+}
+
+impl Items {
+    // How many items are left, without counting them one by one.
+    fn remaining(&self) -> u128 {
+        match self {
+            Self::Objects(objects) => objects.len() as u128,
+            Self::Integers(integers) => {
+                let (start, end) = (*integers.start() as i128, *integers.end() as i128);
+                if integers.is_empty() {
+                    0
+                } else {
+                    (end - start + 1) as u128
+                }
+            }
+            Self::Floats {
+                current,
+                end,
+                inclusive,
+            } => {
+                let span = end - current;
+                if !(span > 0. || (*inclusive && span == 0.)) {
+                    0
+                } else if *inclusive {
+                    span.floor() as u128 + 1
+                } else {
+                    span.ceil() as u128
+                }
+            }
+        }
+    }
+}
+
+impl Iterator for Items {
+    type Item = Object;
+
+    fn next(&mut self) -> Option<Object> {
+        match self {
+            Self::Objects(objects) => objects.next(),
+            Self::Integers(integers) => integers
+                .next()
+                .map(|integer| Object::Integer(integer, Meta::default())),
+            Self::Floats {
+                current,
+                end,
+                inclusive,
+            } => {
+                if *current < *end || (*inclusive && *current == *end) {
+                    let item = Object::Number(*current, Meta::default());
+                    *current += 1.;
+                    Some(item)
+                } else {
+                    None
+                }
+            }
+        }
+    }
+}
+
+/// Builds the numbers of `start..end`, or of `start..=end` if the operator is inclusive.
+pub fn range(
+    start: Object,
+    end: Object,
+    operator: &TokenType,
+    position: Position,
+) -> Result<Object, Error> {
+    let range = format!("{}{}{}", start, operator, end);
+    let items = range_items(start, end, operator, position.clone())?;
+    if items.remaining() > MAX_RANGE_ITEMS {
+        return Err(Error::new(
+            ErrorType::RuntimeError,
+            format!("The range `{}` is too large to build an array", range),
+            position,
+        )
+        .with_note(format!(
+            "an array built from a range can't have more than {} items",
+            MAX_RANGE_ITEMS
+        ))
+        .with_help("count it in a `for` loop, which doesn't build the array".to_string()));
+    }
+    Ok(Object::Array(items.collect(), Meta::default()))
+}
+
+/// Counts the numbers of `start..end` one at a time, like `range` but without building the array.
+pub fn range_items(
+    start: Object,
+    end: Object,
+    operator: &TokenType,
+    position: Position,
+) -> Result<Items, Error> {
+    let inclusive = operator == &TokenType::DotDotEqual;
+    match promote(start, end) {
+        (Object::Integer(start, ..), Object::Integer(end, ..)) => {
+            let end = if inclusive {
+                Some(end)
+            } else {
+                end.checked_sub(1)
+            };
+            match end {
+                Some(end) => Ok(Items::Integers(start..=end)),
+                // An exclusive range that ends at the smallest integer is empty.
+                None => Ok(Items::Objects(Vec::new().into_iter())),
+            }
+        }
+
+        (Object::Number(start, ..), Object::Number(end, ..)) => {
+            let is_empty = !(start < end || (inclusive && start == end));
+            let is_exact = start.abs() < MAX_EXACT_FLOAT && end.abs() < MAX_EXACT_FLOAT;
+            if !is_empty && !is_exact {
+                return Err(Error::new(
+                    ErrorType::RuntimeError,
+                    format!(
+                        "The range `{}{}{}` can't be counted one by one",
+                        start, operator, end
+                    ),
+                    position,
+                )
+                .with_note(format!(
+                    "a float can't add 1 to numbers beyond {}",
+                    MAX_EXACT_FLOAT
+                ))
+                .with_help("use integer bounds, e.g. `int(start)..int(end)`".to_string()));
+            }
+            Ok(Items::Floats {
+                current: start,
+                end,
+                inclusive,
+            })
+        }
+
+        _ => Err(Error::new(
+            ErrorType::RuntimeError,
+            format!("Type mismatch, `{}` expects number on both side", operator),
+            position,
+        )),
+    }
+}
+
+/// Returns the items a `for` loop walks through: the elements of an array, the keys of a map or
+/// the characters of a string.
+pub fn items(iterable: Object, position: Position) -> Result<Items, Error> {
+    let objects = match iterable {
+        Object::Array(array, ..) => array,
+        Object::Map(map, ..) => map
+            .into_keys()
+            .map(|key| Object::String(key, Meta::default()))
+            .collect(),
+        Object::String(string, ..) => string
+            .chars()
+            .map(|character| Object::String(character.to_string(), Meta::default()))
+            .collect(),
+        object => {
+            return Err(Error::new(
+                ErrorType::RuntimeError,
+                format!("`{}` is not iterable", object),
+                position,
+            ))
+        }
+    };
+    Ok(Items::Objects(objects.into_iter()))
+}
+
+/// Walks down the indices and replaces the innermost element, e.g. `arr[0][1] = v`.
+pub fn assign_index(
+    target: &mut Object,
+    indices: &[(Object, Position)],
+    value: Object,
+) -> Result<(), Error> {
+    if let Some(((index, position), rest)) = indices.split_first() {
+        if rest.is_empty() {
+            target.set_index(index, value, position.clone())
+        } else {
+            let mut element = target.index(index, position.clone())?;
+            assign_index(&mut element, rest, value)?;
+            target.set_index(index, element, position.clone())
+        }
+    } else {
+        Ok(())
+    }
+}
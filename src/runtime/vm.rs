@@ -0,0 +1,457 @@
+use std::{collections::BTreeMap, rc::Rc};
+
+use crate::common::{
+    error::{Error, ErrorType, StackFrame},
+    object::{Function, Meta, Object},
+    position::Position,
+    token::{Token, TokenType},
+};
+
+use super::{
+    builtin::Builtin,
+    compiler::{Chunk, Instruction},
+    environment::{Closure, Environment, FunctionBindings, VariableBindings},
+    operator::{self, Items},
+};
+
+struct Mark {
+    height: usize,
+    environment: Environment,
+    iterators: usize,
+}
+
+struct Frame {
+    chunk: Rc<Chunk>,
+    ip: usize,
+    // The height of the stack when the function was called.
+    base: usize,
+    // The scope of the caller, it's restored once the function returns.
+    environment: Environment,
+    marks: Vec<Mark>,
+    iterators: Vec<Items>,
+}
+
+impl Frame {
+    fn new(chunk: Rc<Chunk>, base: usize, environment: Environment) -> Self {
+        Self {
+            chunk,
+            ip: 0,
+            base,
+            environment,
+            marks: Vec::new(),
+            iterators: Vec::new(),
+        }
+    }
+}
+
+/// Runs the chunks produced by the compiler. Calls don't recurse on the Rust stack, every call
+/// pushes a frame instead.
+#[derive(Default)]
+pub struct VirtualMachine {
+    variables: Environment,
+    functions: FunctionBindings,
+    stack: Vec<Object>,
+    callers: Vec<Frame>,
+    return_value: Option<Object>,
+    // The user defined functions that are currently running, for the traceback of runtime errors.
+    call_stack: Vec<StackFrame>,
+}
+
+impl VirtualMachine {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the names of every global variable and function, builtins included.
+    pub fn names(&self) -> Vec<String> {
+        let mut names = self.variables.borrow().names();
+        names.extend(self.functions.names());
+        names
+    }
+
+    /// Declares the global `args` variable, the arguments that were passed to the script.
+    pub fn declare_arguments(&mut self, arguments: &[String]) {
+        let arguments = arguments
+            .iter()
+            .map(|argument| Object::String(argument.clone(), Meta::default()))
+            .collect();
+        self.variables.borrow_mut().declare(
+            &Token::new(
+                TokenType::Identifier,
+                "args".to_string(),
+                None,
+                Position::new("builtin".to_string(), 0),
+            ),
+            Object::Array(arguments, Meta::default()),
+        );
+    }
+
+    pub fn run(&mut self, chunk: Chunk) -> Result<(), Error> {
+        let globals = Rc::clone(&self.variables);
+        let frame = Frame::new(Rc::new(chunk), 0, Rc::clone(&globals));
+        let result = self
+            .execute(frame)
+            .map_err(|error| error.with_traceback(&self.call_stack));
+        if result.is_err() {
+            // Drop whatever the failed program left behind.
+            self.variables = globals;
+            self.stack.clear();
+            self.callers.clear();
+            self.return_value = None;
+            self.call_stack.clear();
+        }
+        result
+    }
+
+    fn pop(&mut self) -> Object {
+        self.stack.pop().unwrap_or(Object::Nil(Meta::default()))
+    }
+
+    fn pop_many(&mut self, count: usize) -> Vec<Object> {
+        let start = self.stack.len().saturating_sub(count);
+        self.stack.split_off(start)
+    }
+
+    fn execute(&mut self, mut frame: Frame) -> Result<(), Error> {
+        loop {
+            let chunk = Rc::clone(&frame.chunk);
+            let instruction = match chunk.code.get(frame.ip) {
+                Some(instruction) => instruction,
+                // The end of the script.
+                None => return Ok(()),
+            };
+            let position = chunk.positions[frame.ip].clone();
+            frame.ip += 1;
+
+            match instruction {
+                Instruction::Constant(constant) => {
+                    self.stack.push(chunk.constants[*constant].clone())
+                }
+
+                Instruction::Nil => self.stack.push(Object::Nil(Meta::default())),
+
+                Instruction::Pop => {
+                    self.pop();
+                }
+
+                Instruction::GetVariable(identifier) => {
+                    let identifier = &chunk.identifiers[*identifier];
+                    // A function name evaluates to the function itself, so it can be passed around.
+                    let variable = self.variables.borrow().get(identifier);
+                    let object = variable.or_else(|error| {
+                        self.functions
+                            .get(identifier)
+                            .map(|function| Object::Function(function, Meta::default()))
+                            .map_err(|_| error)
+                    })?;
+                    self.stack.push(object);
+                }
+
+                Instruction::GetCallee(identifier) => {
+                    let identifier = &chunk.identifiers[*identifier];
+                    let variable = self.variables.borrow().get(identifier);
+                    let object = match variable {
+                        Ok(object) => object,
+                        Err(_) => {
+                            Object::Function(self.functions.get(identifier)?, Meta::default())
+                        }
+                    };
+                    self.stack.push(object);
+                }
+
+                Instruction::DefineVariable(identifier) => {
+                    let value = self.pop();
+                    self.variables
+                        .borrow_mut()
+                        .declare(&chunk.identifiers[*identifier], value.clone());
+                    self.stack.push(value);
+                }
+
+                Instruction::SetVariable(identifier) => {
+                    let value = self.pop();
+                    self.variables
+                        .borrow_mut()
+                        .assign(&chunk.identifiers[*identifier], value.clone())?;
+                    self.stack.push(value);
+                }
+
+                Instruction::SetIndex(identifier, positions) => {
+                    let identifier = &chunk.identifiers[*identifier];
+                    let value = self.pop();
+                    let indices = self
+                        .pop_many(positions.len())
+                        .into_iter()
+                        .zip(positions.iter().cloned())
+                        .collect::<Vec<_>>();
+                    let mut variable = self.variables.borrow().get(identifier)?;
+                    operator::assign_index(&mut variable, &indices, value.clone())?;
+                    self.variables.borrow_mut().assign(identifier, variable)?;
+                    self.stack.push(value);
+                }
+
+                Instruction::DefineFunction(prototype) => {
+                    let function = self.closure(&chunk, *prototype);
+                    self.functions
+                        .put(&chunk.prototypes[*prototype].identifier, function);
+                    self.stack.push(Object::Nil(Meta::default()));
+                }
+
+                Instruction::Closure(prototype) => {
+                    let function = self.closure(&chunk, *prototype);
+                    self.stack
+                        .push(Object::Function(Rc::new(function), Meta::default()));
+                }
+
+                Instruction::Binary(operator) => {
+                    let right = self.pop();
+                    let left = self.pop();
+                    self.stack
+                        .push(operator::binary(operator, left, right, position)?);
+                }
+
+                Instruction::Unary(operator) => {
+                    let right = self.pop();
+                    self.stack.push(operator::unary(operator, right, position)?);
+                }
+
+                Instruction::Range(operator) => {
+                    let end = self.pop();
+                    let start = self.pop();
+                    self.stack
+                        .push(operator::range(start, end, operator, position)?);
+                }
+
+                Instruction::Array(length) => {
+                    let elements = self.pop_many(*length);
+                    self.stack.push(Object::Array(elements, Meta::default()));
+                }
+
+                Instruction::Interpolate(length) => {
+                    let string = self
+                        .pop_many(*length)
+                        .iter()
+                        .map(Object::to_string)
+                        .collect();
+                    self.stack.push(Object::String(string, Meta::default()));
+                }
+
+                Instruction::Map(keys) => {
+                    let mut entries = self.pop_many(keys.len() * 2).into_iter();
+                    let mut map = BTreeMap::new();
+                    for position in keys {
+                        if let (Some(key), Some(value)) = (entries.next(), entries.next()) {
+                            map.insert(Object::key(&key, position)?, value);
+                        }
+                    }
+                    self.stack.push(Object::Map(map, Meta::default()));
+                }
+
+                Instruction::Index => {
+                    let index = self.pop();
+                    let object = self.pop();
+                    self.stack.push(object.index(&index, position)?);
+                }
+
+                Instruction::Slice(inclusive) => {
+                    let end = self.pop();
+                    let start = self.pop();
+                    let object = self.pop();
+                    self.stack
+                        .push(object.slice(&start, &end, *inclusive, position)?);
+                }
+
+                Instruction::CheckCall(arguments, names) => match self.stack.last() {
+                    Some(Object::Function(function, ..)) => {
+                        function.check_arity(*arguments, names, position)?
+                    }
+                    object => {
+                        return Err(Error::new(
+                            ErrorType::RuntimeError,
+                            format!(
+                                "`{}` is not callable",
+                                object.cloned().unwrap_or(Object::Nil(Meta::default()))
+                            ),
+                            position,
+                        ))
+                    }
+                },
+
+                Instruction::Call(arguments, names) => {
+                    let arguments = self.pop_many(*arguments + names.len());
+                    let function = match self.pop() {
+                        Object::Function(function, ..) => function,
+                        object => {
+                            return Err(Error::new(
+                                ErrorType::RuntimeError,
+                                format!("`{}` is not callable", object),
+                                position,
+                            ))
+                        }
+                    };
+                    let arguments = function.bind_arguments(arguments, names);
+                    if let Some(callee) = self.call(&function, arguments, position)? {
+                        frame = self.enter(frame, callee);
+                    }
+                }
+
+                Instruction::Return => {
+                    let value = self.pop();
+                    self.stack.truncate(frame.base);
+                    self.variables = Rc::clone(&frame.environment);
+                    self.call_stack.pop();
+                    match self.callers.pop() {
+                        Some(caller) => frame = caller,
+                        None => return Ok(()),
+                    }
+                    self.stack.push(value);
+                }
+
+                Instruction::Jump(address) => frame.ip = *address,
+
+                Instruction::JumpIfFalse(address) => {
+                    if !self.pop().is_true() {
+                        frame.ip = *address;
+                    }
+                }
+
+                Instruction::JumpIfBound(identifier, address) => {
+                    if self
+                        .variables
+                        .borrow()
+                        .contains(&chunk.identifiers[*identifier])
+                    {
+                        frame.ip = *address;
+                    }
+                }
+
+                Instruction::PushScope => {
+                    self.variables = VariableBindings::new(Rc::clone(&self.variables));
+                }
+
+                Instruction::PopScope => {
+                    let parent = self.variables.borrow().parent();
+                    if let Some(parent) = parent {
+                        self.variables = parent;
+                    }
+                }
+
+                Instruction::Iterate => {
+                    let iterable = self.pop();
+                    frame.iterators.push(operator::items(iterable, position)?);
+                }
+
+                Instruction::IterateRange(operator) => {
+                    let end = self.pop();
+                    let start = self.pop();
+                    frame
+                        .iterators
+                        .push(operator::range_items(start, end, operator, position)?);
+                }
+
+                Instruction::ForNext(exit) => {
+                    match frame.iterators.last_mut().and_then(|items| items.next()) {
+                        Some(item) => self.stack.push(item),
+                        None => frame.ip = *exit,
+                    }
+                }
+
+                Instruction::PopIterator => {
+                    frame.iterators.pop();
+                }
+
+                Instruction::Mark => frame.marks.push(Mark {
+                    height: self.stack.len(),
+                    environment: Rc::clone(&self.variables),
+                    iterators: frame.iterators.len(),
+                }),
+
+                Instruction::Unmark => {
+                    frame.marks.pop();
+                }
+
+                Instruction::Unwind(count) => {
+                    let length = frame.marks.len().saturating_sub(*count);
+                    frame.marks.truncate(length);
+                    if let Some(mark) = frame.marks.last() {
+                        self.stack.truncate(mark.height);
+                        self.variables = Rc::clone(&mark.environment);
+                        frame.iterators.truncate(mark.iterators);
+                    }
+                }
+
+                Instruction::StoreReturn => self.return_value = Some(self.pop()),
+
+                Instruction::LoadReturn => {
+                    let value = self
+                        .return_value
+                        .take()
+                        .unwrap_or(Object::Nil(Meta::default()));
+                    self.stack.push(value);
+                }
+            }
+        }
+    }
+
+    /// Creates a function value out of a prototype, that sees the variables of the current scope.
+    fn closure(&self, chunk: &Chunk, prototype: usize) -> Function {
+        let prototype = &chunk.prototypes[prototype];
+        Function {
+            identifier: prototype.identifier.clone(),
+            paramiters: prototype.paramiters.clone(),
+            block: None,
+            captured: Some(Closure::capture(
+                Rc::clone(&self.variables),
+                Some(Rc::clone(&prototype.chunk)),
+            )),
+            doc: prototype.doc.clone(),
+        }
+    }
+
+    /// Calls a builtin right away and pushes it's result. For any other function the frame
+    /// it should run in is returned. The arguments are the ones `Function::bind_arguments` returns,
+    /// the parameters that have None are left for the function to declare with their defaults.
+    fn call(
+        &mut self,
+        function: &Function,
+        arguments: Vec<Option<Object>>,
+        position: Position,
+    ) -> Result<Option<Frame>, Error> {
+        let closure = Closure::of(function);
+        let chunk = match closure.and_then(|closure| closure.chunk.as_ref()) {
+            Some(chunk) => Rc::clone(chunk),
+            None => {
+                let arguments = arguments
+                    .into_iter()
+                    .map(|argument| argument.unwrap_or(Object::Nil(Meta::default())))
+                    .collect();
+                let value =
+                    Builtin::try_from(function.identifier.clone())?.execute(arguments, position)?;
+                self.stack.push(value);
+                return Ok(None);
+            }
+        };
+
+        let parent = match closure {
+            Some(closure) => Rc::clone(&closure.scope),
+            None => Rc::clone(&self.variables),
+        };
+        let scope = VariableBindings::new(parent);
+        for (param, argument) in function.paramiters.iter().zip(arguments) {
+            if let Some(value) = argument {
+                scope.borrow_mut().declare(&param.identifier, value);
+            }
+        }
+        let callee = Frame::new(chunk, self.stack.len(), Rc::clone(&self.variables));
+        self.variables = scope;
+        self.call_stack.push(StackFrame::new(
+            function.identifier.lexeme.clone(),
+            position,
+        ));
+        Ok(Some(callee))
+    }
+
+    fn enter(&mut self, caller: Frame, callee: Frame) -> Frame {
+        self.callers.push(caller);
+        callee
+    }
+}
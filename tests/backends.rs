@@ -0,0 +1,148 @@
+use std::process::{Command, Output};
+
+// Runs the code with the interpreter and with the VM.
+fn run(code: &str) -> Vec<(&'static [&'static str], Output)> {
+    [&[][..], &["--vm"][..]]
+        .into_iter()
+        .map(|options| {
+            let output = Command::new(env!("CARGO_BIN_EXE_func"))
+                .args(options)
+                .args(["-e", code])
+                .output()
+                .unwrap();
+            (options, output)
+        })
+        .collect()
+}
+
+// Both backends have to print the expected output.
+fn assert_output(code: &str, expected: &str) {
+    for (options, output) in run(code) {
+        assert_eq!(
+            String::from_utf8_lossy(&output.stdout),
+            expected,
+            "options: {:?}, stderr: {}",
+            options,
+            String::from_utf8_lossy(&output.stderr)
+        );
+    }
+}
+
+// Both backends have to fail with the expected message.
+fn assert_error(code: &str, message: &str) {
+    for (options, output) in run(code) {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        assert!(
+            !output.status.success() && stderr.contains(message),
+            "options: {:?}, stderr: {}",
+            options,
+            stderr
+        );
+    }
+}
+
+#[test]
+fn return_inside_call_argument() {
+    assert_output(
+        r#"func f(x) { writeln(if x { return "early" } else { "no" }) "late" } writeln(f(true))"#,
+        "early\n",
+    );
+}
+
+#[test]
+fn return_outside_of_functions() {
+    assert_output(
+        r#"let message = if true { return "block" } else { "no" } writeln(message)"#,
+        "block\n",
+    );
+    assert_output("writeln([{ return 1 }, 2])", "[1,2,]\n");
+    assert_output(
+        r#"for i in 0..3 { if i == 1 { return nil } writeln(i) } writeln("after")"#,
+        "0\nafter\n",
+    );
+}
+
+#[test]
+fn loop_control_inside_expressions() {
+    assert_output(
+        "for i in 0..4 { writeln(if i == 2 { continue } else { i }) }",
+        "0\n1\n3\n",
+    );
+    assert_output(
+        "for i in 0..3 { writeln([if i == 1 { continue } else { i }]) }",
+        "[0,]\n[2,]\n",
+    );
+    assert_output(
+        r#"for i in 0..3 { writeln({"k": if i == 1 { break } else { i }}) }"#,
+        "{k: 0,}\n",
+    );
+    assert_output(
+        r#"for i in 0..3 { writeln("i=${if i == 1 { continue } else { i }}") }"#,
+        "i=0\ni=2\n",
+    );
+}
+
+#[test]
+fn large_range_outside_of_for() {
+    assert_error(
+        "let r = 0..100000000000",
+        "The range `0..100000000000` is too large to build an array",
+    );
+    assert_error(
+        "writeln(len(0..=10000000))",
+        "The range `0..=10000000` is too large to build an array",
+    );
+    assert_output(
+        "for i in 0..100000000000 { if i == 2 { break } writeln(i) }",
+        "0\n1\n",
+    );
+}
+
+#[test]
+fn len_counts_characters() {
+    assert_output(
+        r#"let s = "héllo" writeln(len(s)) writeln(s[len(s) - 1])"#,
+        "5\no\n",
+    );
+}
+
+#[test]
+fn function_body_is_resolved_at_its_first_call() {
+    assert_error(
+        "func f() { y } f() let y = 1",
+        "Variable `y` doesn't exist.",
+    );
+    assert_error(
+        "func f() { y } if true { let y = 1 f() }",
+        "Variable `y` doesn't exist.",
+    );
+    assert_output("func f() { y } let y = 1 writeln(f())", "1\n");
+    assert_output(
+        r#"func a(n) { if n > 0 { b(n - 1) } else { "done" } } func b(n) { a(n) } writeln(a(3))"#,
+        "done\n",
+    );
+}
+
+#[test]
+fn smallest_integer_literal() {
+    assert_output(
+        "writeln(-9223372036854775808 == -9223372036854775807 - 1)",
+        "true\n",
+    );
+    assert_error(
+        "writeln(5 -9223372036854775808)",
+        "The integer `9223372036854775808` is too large",
+    );
+}
+
+#[test]
+fn integers_compare_with_floats_exactly() {
+    assert_output(
+        "writeln(9007199254740993 == 9007199254740992.0) writeln([9007199254740993] == [9007199254740992.0])",
+        "false\nfalse\n",
+    );
+    assert_output(
+        "writeln(9007199254740993 > 9007199254740992.0) writeln([1] == [1.0])",
+        "true\ntrue\n",
+    );
+}